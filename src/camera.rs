@@ -1,5 +1,343 @@
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
 use std::f32::consts::PI;
+use std::ops::RangeInclusive;
+
+// Campo de visión admitido en grados, tanto por --fov como por las teclas y los recorridos
+pub const FOV_RANGE: RangeInclusive<f32> = 10.0..=150.0;
+
+// Eje al que se aplica el campo de visión; el otro sale de la proporción de la imagen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SensorFit {
+    Vertical,
+    Horizontal,
+}
+
+impl SensorFit {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vertical" => Some(SensorFit::Vertical),
+            "horizontal" => Some(SensorFit::Horizontal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionKind {
+    Perspective,
+    // Rayos paralelos: el tamaño aparente no depende de la distancia
+    Orthographic,
+    // Panorama de 360°: longitud en X y latitud en Y, con el centro de la imagen hacia delante
+    Equirectangular,
+}
+
+impl ProjectionKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "perspective" => Some(ProjectionKind::Perspective),
+            "orthographic" => Some(ProjectionKind::Orthographic),
+            "equirect" => Some(ProjectionKind::Equirectangular),
+            _ => None,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectionKind::Perspective => "perspective",
+            ProjectionKind::Orthographic => "orthographic",
+            ProjectionKind::Equirectangular => "equirect",
+        }
+    }
+}
+
+// Vistas predefinidas; isométrica y dimétrica son ortográficas con ángulos de cámara fijos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViewPreset {
+    Perspective,
+    Orthographic,
+    Isometric,
+    Dimetric,
+    Panorama,
+}
+
+impl ViewPreset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "perspective" => Some(ViewPreset::Perspective),
+            "orthographic" | "ortho" => Some(ViewPreset::Orthographic),
+            "isometric" => Some(ViewPreset::Isometric),
+            "dimetric" => Some(ViewPreset::Dimetric),
+            "equirect" | "panorama" => Some(ViewPreset::Panorama),
+            _ => None,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            ViewPreset::Perspective => "perspective",
+            ViewPreset::Orthographic => "orthographic",
+            ViewPreset::Isometric => "isometric",
+            ViewPreset::Dimetric => "dimetric",
+            ViewPreset::Panorama => "equirect",
+        }
+    }
+    
+    pub fn next(&self) -> Self {
+        match self {
+            ViewPreset::Perspective => ViewPreset::Orthographic,
+            ViewPreset::Orthographic => ViewPreset::Isometric,
+            ViewPreset::Isometric => ViewPreset::Dimetric,
+            ViewPreset::Dimetric => ViewPreset::Panorama,
+            ViewPreset::Panorama => ViewPreset::Perspective,
+        }
+    }
+    
+    pub fn kind(&self) -> ProjectionKind {
+        match self {
+            ViewPreset::Perspective => ProjectionKind::Perspective,
+            ViewPreset::Panorama => ProjectionKind::Equirectangular,
+            _ => ProjectionKind::Orthographic,
+        }
+    }
+    
+    // (yaw, pitch) de la cámara orbital: diagonal a 45° y elevación de 35.26° (isométrica, ejes iguales)
+    // o de 30° (dimétrica, la proporción 2:1 del pixel art)
+    pub fn orbit_angles(&self) -> Option<(f32, f32)> {
+        match self {
+            ViewPreset::Isometric => Some((PI / 4.0, (1.0 / 2.0f32.sqrt()).atan())),
+            ViewPreset::Dimetric => Some((PI / 4.0, PI / 6.0)),
+            _ => None,
+        }
+    }
+}
+
+// Proyección de la cámara: convierte coordenadas de pantalla normalizadas en rayos de cámara
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    pub kind: ProjectionKind,
+    pub fov_deg: f32,
+    pub fit: SensorFit,
+    pub aspect: f32,
+    // Alto de la vista ortográfica en unidades de mundo
+    pub ortho_height: f32,
+    pub near: f32,
+    pub far: f32,
+    // Lente delgada: radio de apertura (0 = todo enfocado) y distancia al plano de enfoque
+    pub aperture: f32,
+    pub focus_distance: f32,
+    pub matrix: Mat4,
+    inverse: Mat4,
+}
+
+impl Projection {
+    pub fn new(fov_deg: f32, fit: SensorFit, aspect: f32) -> Self {
+        let mut projection = Projection {
+            kind: ProjectionKind::Perspective,
+            fov_deg,
+            fit,
+            aspect,
+            ortho_height: 12.0,
+            near: 0.1,
+            far: 200.0,
+            aperture: 0.0,
+            focus_distance: 10.0,
+            matrix: Mat4::identity(),
+            inverse: Mat4::identity(),
+        };
+        projection.update();
+        projection
+    }
+    
+    pub fn set_fov(&mut self, fov_deg: f32) {
+        self.fov_deg = fov_deg.clamp(*FOV_RANGE.start(), *FOV_RANGE.end());
+        self.update();
+    }
+    
+    pub fn set_aspect(&mut self, aspect: f32) {
+        self.aspect = aspect;
+        self.update();
+    }
+    
+    pub fn set_kind(&mut self, kind: ProjectionKind) {
+        self.kind = kind;
+        self.update();
+    }
+    
+    pub fn set_ortho_height(&mut self, ortho_height: f32) {
+        self.ortho_height = ortho_height.clamp(1.0, 100.0);
+        self.update();
+    }
+    
+    // Tangente del semiángulo vertical, teniendo en cuenta a qué eje se ajusta el FOV
+    pub fn tan_half_fov_y(&self) -> f32 {
+        let tan_half = (self.fov_deg.to_radians() * 0.5).tan();
+        match self.fit {
+            SensorFit::Vertical => tan_half,
+            SensorFit::Horizontal => tan_half / self.aspect,
+        }
+    }
+    
+    // Ángulo que cubre un píxel a distancia 1, para el filtrado de texturas
+    pub fn pixel_spread(&self, height: usize) -> f32 {
+        match self.kind {
+            ProjectionKind::Perspective => 2.0 * self.tan_half_fov_y() / height as f32,
+            // El píxel ortográfico mide lo mismo a cualquier distancia: aquí es su ancho en unidades de mundo
+            ProjectionKind::Orthographic => self.ortho_height / height as f32,
+            ProjectionKind::Equirectangular => PI / height as f32,
+        }
+    }
+    
+    // Rayo en espacio de cámara (-Z hacia delante) para un punto NDC en [-1, 1]: (origen, dirección)
+    pub fn unproject(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        match self.kind {
+            ProjectionKind::Perspective => {
+                let point = self.inverse * Vec4::new(ndc_x, ndc_y, 1.0, 1.0);
+                (Vec3::new(0.0, 0.0, 0.0), nalgebra_glm::normalize(&(point.xyz() / point.w)))
+            }
+            ProjectionKind::Orthographic => {
+                let point = self.inverse * Vec4::new(ndc_x, ndc_y, 0.0, 1.0);
+                (Vec3::new(point.x, point.y, 0.0), Vec3::new(0.0, 0.0, -1.0))
+            }
+            ProjectionKind::Equirectangular => {
+                let longitude = ndc_x * PI;
+                let latitude = ndc_y * PI * 0.5;
+                let direction = Vec3::new(longitude.sin() * latitude.cos(), latitude.sin(), -longitude.cos() * latitude.cos());
+                (Vec3::new(0.0, 0.0, 0.0), direction)
+            }
+        }
+    }
+    
+    fn update(&mut self) {
+        self.matrix = match self.kind {
+            ProjectionKind::Perspective => {
+                let fov_y = 2.0 * self.tan_half_fov_y().atan();
+                nalgebra_glm::perspective(self.aspect, fov_y, self.near, self.far)
+            }
+            ProjectionKind::Orthographic => {
+                let half_height = self.ortho_height * 0.5;
+                let half_width = half_height * self.aspect;
+                nalgebra_glm::ortho(-half_width, half_width, -half_height, half_height, self.near, self.far)
+            }
+            // No es una proyección lineal: los rayos se calculan directamente en `unproject`
+            ProjectionKind::Equirectangular => Mat4::identity(),
+        };
+        self.inverse = self.matrix.try_inverse().unwrap_or_else(Mat4::identity);
+    }
+}
+
+// Interfaz común de las cámaras: posición y base ortonormal, de las que sale cada rayo primario
+pub trait Camera {
+    fn eye(&self) -> Vec3;
+    // (derecha, arriba, adelante)
+    fn basis(&self) -> (Vec3, Vec3, Vec3);
+    
+    // Posición y base en el instante `time`; las cámaras fijas ignoran el tiempo, las que siguen
+    // un recorrido se mueven durante la exposición y producen desenfoque de movimiento
+    fn pose_at(&self, _time: f32) -> (Vec3, (Vec3, Vec3, Vec3)) {
+        (self.eye(), self.basis())
+    }
+    
+    // Rayo primario (origen, dirección) para un punto NDC de la pantalla en el instante `time`; `lens` es
+    // un punto del disco unidad que, con apertura, desplaza el origen sobre la lente manteniendo el plano de enfoque
+    fn generate_ray(&self, projection: &Projection, ndc_x: f32, ndc_y: f32, lens: (f32, f32), time: f32) -> (Vec3, Vec3) {
+        let (eye, (right, up, forward)) = self.pose_at(time);
+        let to_world = |v: Vec3| right * v.x + up * v.y - forward * v.z;
+        let (local_origin, local_direction) = projection.unproject(ndc_x, ndc_y);
+        let center = eye + to_world(local_origin);
+        let direction = nalgebra_glm::normalize(&to_world(local_direction));
+        if projection.aperture <= 0.0 || projection.kind == ProjectionKind::Equirectangular {
+            return (center, direction);
+        }
+        let focus_point = center + direction * (projection.focus_distance / -local_direction.z);
+        let origin = center + (right * lens.0 + up * lens.1) * projection.aperture;
+        (origin, nalgebra_glm::normalize(&(focus_point - origin)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoLayout {
+    SideBySide,
+    OverUnder,
+}
+
+impl StereoLayout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sbs" | "side-by-side" => Some(StereoLayout::SideBySide),
+            "ou" | "over-under" => Some(StereoLayout::OverUnder),
+            _ => None,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            StereoLayout::SideBySide => "side-by-side",
+            StereoLayout::OverUnder => "over-under",
+        }
+    }
+}
+
+// Par estéreo: separación entre los ojos y distancia a la que ambas imágenes coinciden (paralaje cero)
+#[derive(Debug, Clone, Copy)]
+pub struct StereoSettings {
+    pub layout: StereoLayout,
+    pub eye_separation: f32,
+    pub convergence: f32,
+}
+
+// Un ojo del par: la cámara desplazada a un lado, con los ejes paralelos y el encuadre corrido
+// para converger en el plano `convergence` (sin girar los ojos hacia dentro)
+pub struct StereoEye<'a> {
+    pub camera: &'a dyn Camera,
+    pub offset: f32,
+    pub convergence: f32,
+}
+
+impl Camera for StereoEye<'_> {
+    fn eye(&self) -> Vec3 {
+        let (right, _, _) = self.camera.basis();
+        self.camera.eye() + right * self.offset
+    }
+    
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        self.camera.basis()
+    }
+    
+    fn pose_at(&self, time: f32) -> (Vec3, (Vec3, Vec3, Vec3)) {
+        let (eye, basis) = self.camera.pose_at(time);
+        (eye + basis.0 * self.offset, basis)
+    }
+    
+    fn generate_ray(&self, projection: &Projection, ndc_x: f32, ndc_y: f32, lens: (f32, f32), time: f32) -> (Vec3, Vec3) {
+        let (_, (right, _, forward)) = self.camera.pose_at(time);
+        let (origin, direction) = self.camera.generate_ray(projection, ndc_x, ndc_y, lens, time);
+        let shifted = origin + right * self.offset;
+        let along = nalgebra_glm::dot(&direction, &forward);
+        if along <= 1e-3 {
+            return (shifted, direction);
+        }
+        let target = origin + direction * (self.convergence / along);
+        (shifted, nalgebra_glm::normalize(&(target - shifted)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Orbit,
+    Fly,
+    // Sigue el recorrido por keyframes cargado o generado
+    Path,
+}
+
+impl CameraMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CameraMode::Orbit => "orbit",
+            CameraMode::Fly => "fly",
+            CameraMode::Path => "path",
+        }
+    }
+}
 
 pub struct OrbitCamera {
     pub target: Vec3,
@@ -38,11 +376,23 @@ impl OrbitCamera {
         self.update();
     }
     
+    pub fn set_angles(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = 0.0;
+        self.pitch = 0.0;
+        self.orbit(yaw, pitch);
+    }
+    
     pub fn zoom(&mut self, delta: f32) {
         self.distance = (self.distance + delta).max(1.0).min(20.0);
         self.update();
     }
     
+    // Desplaza el objetivo (y con él la cámara) en el plano de la vista
+    pub fn pan(&mut self, delta_right: f32, delta_up: f32) {
+        self.target += self.right * delta_right + self.up * delta_up;
+        self.update();
+    }
+    
     fn update(&mut self) {
         let x = self.distance * self.pitch.cos() * self.yaw.cos();
         let y = self.distance * self.pitch.sin();
@@ -55,8 +405,123 @@ impl OrbitCamera {
         self.up = nalgebra_glm::cross(&self.right, &self.forward);
     }
     
-    pub fn get_ray_direction(&self, screen_x: f32, screen_y: f32) -> Vec3 {
-        let direction = screen_x * self.right + screen_y * self.up + self.forward;
-        nalgebra_glm::normalize(&direction)
+}
+
+impl Camera for OrbitCamera {
+    fn eye(&self) -> Vec3 {
+        self.eye
+    }
+    
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        (self.right, self.up, self.forward)
+    }
+}
+
+// Cámara libre en primera persona: se mueve en su propio marco y mira con yaw/pitch
+pub struct FlyCamera {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub speed: f32,
+    pub up: Vec3,
+    pub right: Vec3,
+    pub forward: Vec3,
+}
+
+impl FlyCamera {
+    pub fn new(position: Vec3, yaw: f32, pitch: f32) -> Self {
+        let mut camera = FlyCamera {
+            position,
+            yaw,
+            pitch,
+            speed: 4.0,
+            up: Vec3::new(0.0, 1.0, 0.0),
+            right: Vec3::new(1.0, 0.0, 0.0),
+            forward: Vec3::new(0.0, 0.0, -1.0),
+        };
+        camera.update();
+        camera
+    }
+    
+    // Arranca donde está otra cámara y mirando hacia el mismo lado
+    pub fn from_camera(camera: &dyn Camera) -> Self {
+        let (_, _, forward) = camera.basis();
+        FlyCamera::looking_at(camera.eye(), camera.eye() + forward)
+    }
+    
+    pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+        let forward = nalgebra_glm::normalize(&(target - position));
+        FlyCamera::new(position, forward.z.atan2(forward.x), forward.y.clamp(-1.0, 1.0).asin())
+    }
+    
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        self.yaw = (self.yaw + delta_yaw) % (2.0 * PI);
+        self.pitch = (self.pitch + delta_pitch).clamp(-PI * 0.49, PI * 0.49);
+        self.update();
+    }
+    
+    // Desplazamiento en unidades de `speed` por segundo: adelante, derecha y vertical del mundo
+    pub fn translate(&mut self, forward: f32, right: f32, up: f32, dt: f32) {
+        let step = self.speed * dt;
+        self.position += self.forward * (forward * step) + self.right * (right * step) + Vec3::new(0.0, up * step, 0.0);
+    }
+    
+    pub fn adjust_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).clamp(0.25, 50.0);
+    }
+    
+    fn update(&mut self) {
+        self.forward = Vec3::new(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.sin(),
+        );
+        self.right = nalgebra_glm::normalize(&nalgebra_glm::cross(&self.forward, &Vec3::new(0.0, 1.0, 0.0)));
+        self.up = nalgebra_glm::cross(&self.right, &self.forward);
+    }
+}
+
+impl Camera for FlyCamera {
+    fn eye(&self) -> Vec3 {
+        self.position
+    }
+    
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        (self.right, self.up, self.forward)
+    }
+}
+
+// Cámara con orientación fija, usada para las caras de un cubemap
+pub struct FixedCamera {
+    pub eye: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+    pub forward: Vec3,
+}
+
+impl Camera for FixedCamera {
+    fn eye(&self) -> Vec3 {
+        self.eye
+    }
+    
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        (self.right, self.up, self.forward)
     }
+}
+
+// Seis cámaras de 90° desde `eye`, orientadas según la convención de cubemap de OpenGL con la que
+// `Skybox` lee las caras (px, nx, py, ny, pz, nz)
+pub fn cube_face_cameras(eye: Vec3) -> Vec<(&'static str, FixedCamera)> {
+    let axis = |x: f32, y: f32, z: f32| Vec3::new(x, y, z);
+    let faces = [
+        ("px", axis(1.0, 0.0, 0.0), axis(0.0, 0.0, -1.0), axis(0.0, 1.0, 0.0)),
+        ("nx", axis(-1.0, 0.0, 0.0), axis(0.0, 0.0, 1.0), axis(0.0, 1.0, 0.0)),
+        ("py", axis(0.0, 1.0, 0.0), axis(1.0, 0.0, 0.0), axis(0.0, 0.0, -1.0)),
+        ("ny", axis(0.0, -1.0, 0.0), axis(1.0, 0.0, 0.0), axis(0.0, 0.0, 1.0)),
+        ("pz", axis(0.0, 0.0, 1.0), axis(1.0, 0.0, 0.0), axis(0.0, 1.0, 0.0)),
+        ("nz", axis(0.0, 0.0, -1.0), axis(-1.0, 0.0, 0.0), axis(0.0, 1.0, 0.0)),
+    ];
+    faces.into_iter()
+        .map(|(name, forward, right, up)| (name, FixedCamera { eye, right, up, forward }))
+        .collect()
 }
\ No newline at end of file
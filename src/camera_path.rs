@@ -0,0 +1,259 @@
+use crate::camera::{Camera, FlyCamera, OrbitCamera};
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+const TURNTABLE_KEYS: usize = 24;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CameraKeyframe {
+    pub time: f32,
+    pub position: Vec3,
+    pub target: Vec3,
+    pub fov: f32,
+}
+
+impl CameraKeyframe {
+    pub fn camera(&self) -> FlyCamera {
+        FlyCamera::looking_at(self.position, self.target)
+    }
+}
+
+// Cámara que sigue un recorrido: `time` es el instante del cuadro, pero cada rayo la evalúa en su propio
+// instante dentro del obturador
+pub struct PathCamera<'a> {
+    pub path: &'a CameraPath,
+    pub time: f32,
+    // Pose en `time`, calculada una sola vez: con el obturador cerrado todos los rayos del cuadro la comparten
+    pose: (Vec3, (Vec3, Vec3, Vec3)),
+}
+
+impl<'a> PathCamera<'a> {
+    pub fn new(path: &'a CameraPath, time: f32) -> Self {
+        let camera = path.sample(time).camera();
+        PathCamera { path, time, pose: (camera.eye(), camera.basis()) }
+    }
+}
+
+impl Camera for PathCamera<'_> {
+    fn eye(&self) -> Vec3 {
+        self.pose.0
+    }
+    
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        self.pose.1
+    }
+    
+    fn pose_at(&self, time: f32) -> (Vec3, (Vec3, Vec3, Vec3)) {
+        if time == self.time {
+            return self.pose;
+        }
+        let camera = self.path.sample(time).camera();
+        (camera.eye(), camera.basis())
+    }
+}
+
+// Recorrido de cámara por keyframes, interpolado con splines Catmull-Rom
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    // Si está definido el recorrido se repite con este periodo, volviendo suavemente al primer keyframe
+    pub period: Option<f32>,
+}
+
+impl CameraPath {
+    pub fn load(path: &str, default_fov: f32) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(path, &text, default_fov)
+    }
+    
+    // Una línea por keyframe: `tiempo  x y z  objetivo_x objetivo_y objetivo_z  [fov]`; `#` inicia un comentario.
+    // `path` solo se usa en los mensajes de error
+    pub fn parse(path: &str, text: &str, default_fov: f32) -> Result<Self, String> {
+        let mut keyframes = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let values: Vec<f32> = line.split_whitespace().map(|v| v.parse()).collect::<Result<_, _>>()
+                .map_err(|_| format!("{}:{}: invalid number", path, number + 1))?;
+            if values.len() != 7 && values.len() != 8 {
+                return Err(format!("{}:{}: expected time, position, target and optional fov", path, number + 1));
+            }
+            let position = Vec3::new(values[1], values[2], values[3]);
+            let target = Vec3::new(values[4], values[5], values[6]);
+            // Sin dirección de vista la base de la cámara saldría con NaN
+            if position == target {
+                return Err(format!("{}:{}: position and target must differ", path, number + 1));
+            }
+            keyframes.push(CameraKeyframe {
+                time: values[0],
+                position,
+                target,
+                fov: values.get(7).copied().unwrap_or(default_fov),
+            });
+        }
+        if keyframes.is_empty() {
+            return Err(format!("{}: no keyframes", path));
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(CameraPath { keyframes, period: None })
+    }
+    
+    // Una vuelta completa alrededor del objetivo de la cámara orbital, a su distancia y altura
+    pub fn turntable(camera: &OrbitCamera, seconds: f32, fov: f32) -> Self {
+        let keyframes = (0..TURNTABLE_KEYS).map(|i| {
+            let fraction = i as f32 / TURNTABLE_KEYS as f32;
+            let yaw = camera.yaw + fraction * 2.0 * PI;
+            let offset = Vec3::new(
+                camera.pitch.cos() * yaw.cos(),
+                camera.pitch.sin(),
+                camera.pitch.cos() * yaw.sin(),
+            ) * camera.distance;
+            CameraKeyframe { time: fraction * seconds, position: camera.target + offset, target: camera.target, fov }
+        }).collect();
+        CameraPath { keyframes, period: Some(seconds) }
+    }
+    
+    pub fn duration(&self) -> f32 {
+        self.period.unwrap_or_else(|| self.keyframes.last().map_or(0.0, |k| k.time) - self.keyframes[0].time)
+    }
+    
+    pub fn sample(&self, time: f32) -> CameraKeyframe {
+        let keys = &self.keyframes;
+        let count = keys.len();
+        if count == 1 {
+            return keys[0];
+        }
+        
+        // Índice del tramo y tiempos de sus extremos; en recorridos cíclicos el último tramo vuelve al primero
+        let (segment, start, end, time) = match self.period {
+            Some(period) => {
+                let t = keys[0].time + (time - keys[0].time).rem_euclid(period);
+                let segment = keys.iter().rposition(|k| k.time <= t).unwrap_or(0);
+                let end = if segment + 1 < count { keys[segment + 1].time } else { keys[0].time + period };
+                (segment, keys[segment].time, end, t)
+            }
+            None => {
+                let t = time.clamp(keys[0].time, keys[count - 1].time);
+                let segment = keys.iter().rposition(|k| k.time <= t).unwrap_or(0).min(count - 2);
+                (segment, keys[segment].time, keys[segment + 1].time, t)
+            }
+        };
+        let u = if end > start { ((time - start) / (end - start)).clamp(0.0, 1.0) } else { 0.0 };
+        
+        let key = |offset: isize| -> &CameraKeyframe {
+            let index = segment as isize + offset;
+            if self.period.is_some() {
+                &keys[index.rem_euclid(count as isize) as usize]
+            } else {
+                &keys[index.clamp(0, count as isize - 1) as usize]
+            }
+        };
+        let (k0, k1, k2, k3) = (key(-1), key(0), key(1), key(2));
+        let position = catmull_rom(&k0.position, &k1.position, &k2.position, &k3.position, u);
+        let mut target = catmull_rom(&k0.target, &k1.target, &k2.target, &k3.target, u);
+        // Entre keyframes válidos la curva del objetivo puede cruzar la de la cámara: se mira como en el keyframe
+        // de inicio del tramo para no quedarse sin dirección de vista
+        if nalgebra_glm::distance2(&position, &target) < 1e-8 {
+            target = position + (k1.target - k1.position);
+        }
+        CameraKeyframe {
+            time,
+            position,
+            target,
+            fov: catmull_rom_scalar(k0.fov, k1.fov, k2.fov, k3.fov, u),
+        }
+    }
+}
+
+fn catmull_rom(p0: &Vec3, p1: &Vec3, p2: &Vec3, p3: &Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+fn catmull_rom_scalar(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn assert_close(a: &Vec3, b: &Vec3) {
+        assert!(nalgebra_glm::distance(a, b) < 1e-4, "{:?} != {:?}", a, b);
+    }
+    
+    #[test]
+    fn parse_skips_comments_and_blank_lines() {
+        let text = "# tiempo posición objetivo\n\n0  0 2 5  0 0 0  # inicio\n   \n2  5 2 0  0 0 0\n";
+        let path = CameraPath::parse("path.txt", text, 90.0).unwrap();
+        assert_eq!(path.keyframes.len(), 2);
+    }
+    
+    #[test]
+    fn fov_column_is_optional() {
+        let path = CameraPath::parse("path.txt", "0  0 2 5  0 0 0  60\n1  5 2 0  0 0 0", 90.0).unwrap();
+        assert_eq!(path.keyframes[0].fov, 60.0);
+        assert_eq!(path.keyframes[1].fov, 90.0);
+        
+        let error = CameraPath::parse("path.txt", "0  0 2 5  0 0", 90.0).unwrap_err();
+        assert_eq!(error, "path.txt:1: expected time, position, target and optional fov");
+        let error = CameraPath::parse("path.txt", "0  0 2 5  0 0 0  60 1", 90.0).unwrap_err();
+        assert_eq!(error, "path.txt:1: expected time, position, target and optional fov");
+    }
+    
+    #[test]
+    fn position_equal_to_target_is_rejected() {
+        let error = CameraPath::parse("path.txt", "0  0 2 5  0 0 0\n1  1 1 1  1 1 1", 90.0).unwrap_err();
+        assert_eq!(error, "path.txt:2: position and target must differ");
+    }
+    
+    #[test]
+    fn keyframes_are_sorted_by_time() {
+        let text = "4  0 2 -5  0 0 0\n0  0 2 5  0 0 0\n2  5 2 0  0 0 0";
+        let path = CameraPath::parse("path.txt", text, 90.0).unwrap();
+        let times: Vec<f32> = path.keyframes.iter().map(|k| k.time).collect();
+        assert_eq!(times, vec![0.0, 2.0, 4.0]);
+    }
+    
+    #[test]
+    fn sample_passes_through_every_keyframe() {
+        let text = "0  0 2 5  0 0 0  70\n1.5  5 3 0  0 1 0\n3  0 2 -5  0 0 0  50\n4  -5 1 0  0 0 0";
+        let path = CameraPath::parse("path.txt", text, 90.0).unwrap();
+        for keyframe in &path.keyframes {
+            let sample = path.sample(keyframe.time);
+            assert_close(&sample.position, &keyframe.position);
+            assert_close(&sample.target, &keyframe.target);
+            assert!((sample.fov - keyframe.fov).abs() < 1e-4);
+        }
+    }
+    
+    #[test]
+    fn sample_keeps_a_view_direction_when_the_curves_cross() {
+        // A mitad del tramo la cámara y el objetivo interpolados coinciden en el origen
+        let text = "0  -1 0 0  1 0 0\n1  1 0 0  -1 0 0";
+        let path = CameraPath::parse("path.txt", text, 90.0).unwrap();
+        let sample = path.sample(0.5);
+        assert!(nalgebra_glm::distance(&sample.position, &sample.target) > 0.1);
+        let (right, up, forward) = sample.camera().basis();
+        assert!([right, up, forward].iter().all(|v| v.iter().all(|c| c.is_finite())));
+    }
+    
+    #[test]
+    fn turntable_wraps_around_its_period() {
+        let mut camera = OrbitCamera::new(Vec3::new(0.0, 1.0, 0.0), 8.0);
+        camera.orbit(0.3, 0.4);
+        let path = CameraPath::turntable(&camera, 6.0, 90.0);
+        assert_close(&path.sample(6.0).position, &path.keyframes[0].position);
+        assert_close(&path.sample(7.3).position, &path.sample(1.3).position);
+        assert_close(&path.sample(-0.5).position, &path.sample(5.5).position);
+        // El último tramo vuelve suavemente al primer keyframe en lugar de detenerse en el último
+        let last = path.keyframes.last().unwrap().time;
+        let between = path.sample((last + 6.0) * 0.5).position;
+        assert!((nalgebra_glm::distance(&between, &camera.target) - 8.0).abs() < 0.1);
+    }
+}
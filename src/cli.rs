@@ -0,0 +1,354 @@
+use crate::camera::{FOV_RANGE, Projection, SensorFit, StereoLayout, StereoSettings, ViewPreset};
+use crate::color::Color;
+use crate::screenshot::ScreenshotSettings;
+use crate::skybox::{CubemapLayout, SkyboxOptions};
+use crate::texture::TextureFilter;
+use nalgebra_glm::Vec3;
+use crate::{HEIGHT, WIDTH};
+
+pub const USAGE: &str = "Usage: proyecto2 [options]
+
+Without --output the interactive window is opened.
+
+Options:
+  --output DIR        Render an image sequence into DIR instead of opening a window
+  --frames N          Number of frames to render offline (default: 1, or the whole camera path)
+  --fps N             Frames per second used to advance the global time (default: 30)
+  --start-time SECS   Global time of the first frame (default: 0)
+  --width N           Image width in pixels (default: 400)
+  --height N          Image height in pixels (default: 300)
+  --fov DEG           Camera field of view, 10 to 150 (default: 90)
+  --fov-axis AXIS     Axis the field of view applies to: vertical or horizontal (default: vertical)
+  --projection P      perspective, orthographic, isometric, dimetric or equirect (default: perspective)
+  --ortho-size H      Height of the orthographic view in world units (default: 12)
+  --mouse-sensitivity X Multiplier for mouse orbit, pan and look speed (default: 1)
+  --scroll-sensitivity X Multiplier for scroll wheel zoom (default: 1)
+  --no-hud            Start with the on-screen statistics hidden (toggle with H)
+  --screenshot-dir DIR Folder for screenshots taken with F12 (default: screenshots)
+  --screenshot-scale N Also re-render screenshots at N times the window size in the background (default: 1)
+  --screenshot-spp N  Samples per pixel for that re-render (default: same as --spp)
+  --bindings FILE     Load key bindings ('action = Key' per line) over the defaults
+  --record FILE       Record every window frame (camera, lens, keys) to FILE
+  --replay FILE       Play back a recorded session instead of reading the keyboard
+  --headless          With --replay, render without a window and print timings
+  --camera-position X,Y,Z Start from a free camera at this point, looking at the diorama
+  --camera-path FILE  Follow keyframes from FILE, one 'time x y z target_x target_y target_z [fov]' per line
+  --turntable SECS    Circle around the diorama once every SECS seconds
+  --cube-faces        Render the six cubemap faces (px, nx, py, ny, pz, nz) of each frame
+  --stereo LAYOUT     Render left/right eye images: sbs (side-by-side) or ou (over-under)
+  --eye-separation X  Distance between the stereo eyes in world units (default: 0.2)
+  --convergence D     Distance at which both eyes line up (default: 10)
+  --aperture R        Lens radius for depth of field, 0 keeps everything sharp (default: 0)
+  --focus-distance D  Distance to the plane in focus (default: 10)
+  --autofocus         Focus on whatever the center of the image hits
+  --texture-filter F  nearest, bilinear or trilinear (default: nearest, toggle with F)
+  --decorations       Add a tree, stained glass and a grate to the diorama
+  --spp N             Samples per pixel, smooths depth of field and edges (default: 1)
+  --shutter F         Fraction of each frame the shutter stays open, for motion blur (default: 0)
+  --hdri FILE         Equirectangular .hdr environment map used as sky and for lighting
+  --hdri-intensity X  Multiplier applied to the environment map radiance (default: 1)
+  --ibl-samples N     Environment light samples per hit (default: 4)
+  --skybox PATH       Cubemap image, or a pattern with {} for six files (default: {}.png)
+  --skybox-layout L   faces, hcross, vcross, hstrip or vstrip (default: detected)
+  --sky-rotation DEG  Rotate the skybox around the vertical axis
+  --horizon-offset DEG Move the skybox horizon up (negative: down)
+  --sky-exposure X    Brightness multiplier for the skybox (default: 1)
+  --physical-sky      Use the analytic Preetham sky with a directional sun light
+  --sun-elevation DEG Sun elevation above the horizon (default: 35)
+  --sun-azimuth DEG   Sun azimuth, 0 = towards -Z, 90 = towards +X (default: 225)
+  --turbidity T       Atmospheric turbidity, 2 = clear to 10 = hazy (default: 3)
+  --clouds            Add raymarched volumetric clouds above the diorama
+  --cloud-coverage X  Fraction of the sky covered by clouds, 0 to 1 (default: 0.45)
+  --wind-speed X      Multiplier for the cloud drift speed (default: 1)
+  --fog DENSITY       Fill the scene with fog of the given density (default: 0, off)
+  --fog-color R,G,B   Fog color, 0-255 per channel (default: 190,200,215)
+  --fog-height Y      Use height fog that is densest at Y and thins out above it
+  --fog-falloff X     How fast height fog thins out per unit of height (default: 0.3)
+  --light-shafts      Scatter the main light inside the fog to show light shafts
+  --no-lava-smoke     Disable the glowing smoke and heat haze above the lava pool
+  -h, --help          Show this message";
+
+#[derive(Debug, Clone)]
+pub struct CliOptions {
+    pub output_dir: Option<String>,
+    pub frames: Option<u32>,
+    pub fps: f32,
+    pub start_time: f32,
+    pub width: usize,
+    pub height: usize,
+    pub fov: f32,
+    pub fov_axis: SensorFit,
+    pub texture_filter: TextureFilter,
+    pub decorations: bool,
+    pub view: ViewPreset,
+    pub ortho_size: f32,
+    pub hud: bool,
+    pub screenshot_dir: String,
+    pub screenshot_scale: usize,
+    pub screenshot_samples: u32,
+    pub bindings: Option<String>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub headless: bool,
+    pub camera_position: Option<Vec3>,
+    pub cube_faces: bool,
+    pub camera_path: Option<String>,
+    pub turntable: Option<f32>,
+    pub stereo: Option<StereoLayout>,
+    pub eye_separation: f32,
+    pub convergence: f32,
+    pub aperture: f32,
+    pub focus_distance: f32,
+    pub autofocus: bool,
+    pub samples: u32,
+    pub shutter: f32,
+    pub mouse_sensitivity: f32,
+    pub scroll_sensitivity: f32,
+    pub hdri: Option<String>,
+    pub hdri_intensity: f32,
+    pub ibl_samples: u32,
+    pub skybox_path: String,
+    pub skybox_layout: Option<CubemapLayout>,
+    pub skybox_options: SkyboxOptions,
+    pub physical_sky: bool,
+    pub sun_elevation: f32,
+    pub sun_azimuth: f32,
+    pub turbidity: f32,
+    pub clouds: bool,
+    pub cloud_coverage: f32,
+    pub wind_speed: f32,
+    pub fog_density: f32,
+    pub fog_color: Color,
+    pub fog_height: Option<f32>,
+    pub fog_falloff: f32,
+    pub light_shafts: bool,
+    pub lava_smoke: bool,
+    pub show_help: bool,
+}
+
+impl Default for CliOptions {
+    fn default() -> Self {
+        CliOptions {
+            output_dir: None,
+            frames: None,
+            fps: 30.0,
+            start_time: 0.0,
+            width: WIDTH,
+            height: HEIGHT,
+            fov: 90.0,
+            fov_axis: SensorFit::Vertical,
+            texture_filter: TextureFilter::Nearest,
+            decorations: false,
+            view: ViewPreset::Perspective,
+            ortho_size: 12.0,
+            hud: true,
+            screenshot_dir: "screenshots".to_string(),
+            screenshot_scale: 1,
+            screenshot_samples: 0,
+            bindings: None,
+            record: None,
+            replay: None,
+            headless: false,
+            camera_position: None,
+            cube_faces: false,
+            camera_path: None,
+            turntable: None,
+            stereo: None,
+            eye_separation: 0.2,
+            convergence: 10.0,
+            aperture: 0.0,
+            focus_distance: 10.0,
+            autofocus: false,
+            samples: 1,
+            shutter: 0.0,
+            mouse_sensitivity: 1.0,
+            scroll_sensitivity: 1.0,
+            hdri: None,
+            hdri_intensity: 1.0,
+            ibl_samples: 4,
+            skybox_path: "{}.png".to_string(),
+            skybox_layout: None,
+            skybox_options: SkyboxOptions::default(),
+            physical_sky: false,
+            sun_elevation: 35.0,
+            sun_azimuth: 225.0,
+            turbidity: 3.0,
+            clouds: false,
+            cloud_coverage: 0.45,
+            wind_speed: 1.0,
+            fog_density: 0.0,
+            fog_color: Color::new(190, 200, 215),
+            fog_height: None,
+            fog_falloff: 0.3,
+            light_shafts: false,
+            lava_smoke: true,
+            show_help: false,
+        }
+    }
+}
+
+impl CliOptions {
+    pub fn parse() -> Result<Self, String> {
+        Self::from_args(std::env::args().skip(1))
+    }
+    
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut options = CliOptions::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--output" => options.output_dir = Some(next_value(&mut args, &arg)?),
+                "--frames" => options.frames = Some(parse_value(&mut args, &arg)?),
+                "--fps" => options.fps = parse_value(&mut args, &arg)?,
+                "--start-time" => options.start_time = parse_value(&mut args, &arg)?,
+                "--width" => options.width = parse_value(&mut args, &arg)?,
+                "--height" => options.height = parse_value(&mut args, &arg)?,
+                "--fov" => options.fov = parse_value(&mut args, &arg)?,
+                "--fov-axis" => {
+                    let name = next_value(&mut args, &arg)?;
+                    options.fov_axis = SensorFit::from_name(&name)
+                        .ok_or_else(|| format!("Invalid value for {}: {}", arg, name))?;
+                }
+                "--texture-filter" => {
+                    let name = next_value(&mut args, &arg)?;
+                    options.texture_filter = TextureFilter::from_name(&name)
+                        .ok_or_else(|| format!("Invalid value for {}: {}", arg, name))?;
+                }
+                "--decorations" => options.decorations = true,
+                "--projection" => {
+                    let name = next_value(&mut args, &arg)?;
+                    options.view = ViewPreset::from_name(&name)
+                        .ok_or_else(|| format!("Invalid value for {}: {}", arg, name))?;
+                }
+                "--ortho-size" => options.ortho_size = parse_value(&mut args, &arg)?,
+                "--no-hud" => options.hud = false,
+                "--screenshot-dir" => options.screenshot_dir = next_value(&mut args, &arg)?,
+                "--screenshot-scale" => options.screenshot_scale = parse_value(&mut args, &arg)?,
+                "--screenshot-spp" => options.screenshot_samples = parse_value(&mut args, &arg)?,
+                "--bindings" => options.bindings = Some(next_value(&mut args, &arg)?),
+                "--record" => options.record = Some(next_value(&mut args, &arg)?),
+                "--replay" => options.replay = Some(next_value(&mut args, &arg)?),
+                "--headless" => options.headless = true,
+                "--camera-position" => options.camera_position = Some(parse_vec3(&mut args, &arg)?),
+                "--cube-faces" => options.cube_faces = true,
+                "--camera-path" => options.camera_path = Some(next_value(&mut args, &arg)?),
+                "--turntable" => options.turntable = Some(parse_value(&mut args, &arg)?),
+                "--stereo" => {
+                    let name = next_value(&mut args, &arg)?;
+                    options.stereo = Some(StereoLayout::from_name(&name)
+                        .ok_or_else(|| format!("Invalid value for {}: {}", arg, name))?);
+                }
+                "--eye-separation" => options.eye_separation = parse_value(&mut args, &arg)?,
+                "--convergence" => options.convergence = parse_value(&mut args, &arg)?,
+                "--aperture" => options.aperture = parse_value(&mut args, &arg)?,
+                "--focus-distance" => options.focus_distance = parse_value(&mut args, &arg)?,
+                "--autofocus" => options.autofocus = true,
+                "--spp" => options.samples = parse_value(&mut args, &arg)?,
+                "--shutter" => options.shutter = parse_value(&mut args, &arg)?,
+                "--mouse-sensitivity" => options.mouse_sensitivity = parse_value(&mut args, &arg)?,
+                "--scroll-sensitivity" => options.scroll_sensitivity = parse_value(&mut args, &arg)?,
+                "--hdri" => options.hdri = Some(next_value(&mut args, &arg)?),
+                "--hdri-intensity" => options.hdri_intensity = parse_value(&mut args, &arg)?,
+                "--ibl-samples" => options.ibl_samples = parse_value(&mut args, &arg)?,
+                "--skybox" => options.skybox_path = next_value(&mut args, &arg)?,
+                "--skybox-layout" => {
+                    let name = next_value(&mut args, &arg)?;
+                    options.skybox_layout = Some(CubemapLayout::from_name(&name)
+                        .ok_or_else(|| format!("Invalid value for {}: {}", arg, name))?);
+                }
+                "--sky-rotation" => options.skybox_options.rotation_deg = parse_value(&mut args, &arg)?,
+                "--horizon-offset" => options.skybox_options.horizon_offset_deg = parse_value(&mut args, &arg)?,
+                "--sky-exposure" => options.skybox_options.exposure = parse_value(&mut args, &arg)?,
+                "--physical-sky" => options.physical_sky = true,
+                "--sun-elevation" => options.sun_elevation = parse_value(&mut args, &arg)?,
+                "--sun-azimuth" => options.sun_azimuth = parse_value(&mut args, &arg)?,
+                "--turbidity" => options.turbidity = parse_value(&mut args, &arg)?,
+                "--clouds" => options.clouds = true,
+                "--cloud-coverage" => options.cloud_coverage = parse_value(&mut args, &arg)?,
+                "--wind-speed" => options.wind_speed = parse_value(&mut args, &arg)?,
+                "--fog" => options.fog_density = parse_value(&mut args, &arg)?,
+                "--fog-color" => options.fog_color = parse_color(&mut args, &arg)?,
+                "--fog-height" => options.fog_height = Some(parse_value(&mut args, &arg)?),
+                "--fog-falloff" => options.fog_falloff = parse_value(&mut args, &arg)?,
+                "--light-shafts" => options.light_shafts = true,
+                "--no-lava-smoke" => options.lava_smoke = false,
+                "-h" | "--help" => options.show_help = true,
+                other => return Err(format!("Unknown argument: {}", other)),
+            }
+        }
+        
+        if options.fps <= 0.0 {
+            return Err("--fps must be greater than zero".to_string());
+        }
+        if options.width == 0 || options.height == 0 {
+            return Err("--width and --height must be greater than zero".to_string());
+        }
+        if options.record.is_some() && (options.replay.is_some() || options.output_dir.is_some()) {
+            return Err("--record only works in the interactive window".to_string());
+        }
+        if !(0.0..=1.0).contains(&options.shutter) {
+            return Err("--shutter must be between 0 and 1".to_string());
+        }
+        if options.screenshot_scale == 0 {
+            return Err("--screenshot-scale must be at least 1".to_string());
+        }
+        if options.turntable.is_some_and(|seconds| seconds <= 0.0) {
+            return Err("--turntable must be greater than zero".to_string());
+        }
+        if !FOV_RANGE.contains(&options.fov) {
+            return Err(format!("--fov must be between {} and {} degrees", FOV_RANGE.start(), FOV_RANGE.end()));
+        }
+        Ok(options)
+    }
+    
+    // Proyección de la cámara con la proporción de la imagen de salida
+    pub fn projection(&self) -> Projection {
+        let mut projection = Projection::new(self.fov, self.fov_axis, self.width as f32 / self.height as f32);
+        projection.set_kind(self.view.kind());
+        projection.set_ortho_height(self.ortho_size);
+        projection.aperture = self.aperture.max(0.0);
+        projection.focus_distance = self.focus_distance;
+        projection
+    }
+    
+    pub fn stereo_settings(&self, layout: StereoLayout) -> StereoSettings {
+        StereoSettings { layout, eye_separation: self.eye_separation, convergence: self.convergence.max(0.1) }
+    }
+    
+    pub fn screenshot_settings(&self) -> ScreenshotSettings {
+        ScreenshotSettings { directory: self.screenshot_dir.clone(), scale: self.screenshot_scale, samples: self.screenshot_samples }
+    }
+    
+    // Tiempo global del cuadro `frame` en una secuencia offline
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        self.start_time + frame as f32 / self.fps
+    }
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("Missing value for {}", flag))
+}
+
+fn parse_value<T: std::str::FromStr, I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<T, String> {
+    let value = next_value(args, flag)?;
+    value.parse().map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+fn parse_vec3<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<Vec3, String> {
+    let value = next_value(args, flag)?;
+    let components: Vec<f32> = value.split(',').map(|c| c.trim().parse()).collect::<Result<_, _>>()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))?;
+    match components.as_slice() {
+        [x, y, z] => Ok(Vec3::new(*x, *y, *z)),
+        _ => Err(format!("Invalid value for {}: {} (expected X,Y,Z)", flag, value)),
+    }
+}
+
+fn parse_color<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<Color, String> {
+    let value = next_value(args, flag)?;
+    let channels: Vec<u8> = value.split(',').map(|c| c.trim().parse()).collect::<Result<_, _>>()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))?;
+    match channels.as_slice() {
+        [r, g, b] => Ok(Color::new(*r, *g, *b)),
+        _ => Err(format!("Invalid value for {}: {} (expected R,G,B)", flag, value)),
+    }
+}
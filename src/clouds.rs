@@ -0,0 +1,95 @@
+use crate::media::henyey_greenstein;
+use crate::noise::fbm;
+use nalgebra_glm::{Vec3, dot};
+use std::f32::consts::PI;
+
+const VIEW_STEPS: u32 = 24;
+const LIGHT_STEPS: u32 = 4;
+
+// Capa de nubes volumétricas entre dos alturas, trazada por raymarching sobre ruido fBm
+#[derive(Debug, Clone)]
+pub struct CloudLayer {
+    pub base_height: f32,
+    pub top_height: f32,
+    pub coverage: f32,
+    pub density: f32,
+    pub scale: f32,
+    pub wind: Vec3,
+}
+
+// Luz que reciben las nubes: el sol directo y el cielo cenital como ambiente
+#[derive(Debug, Clone, Copy)]
+pub struct CloudLighting {
+    pub sun_direction: Vec3,
+    pub sun_color: Vec3,
+    pub ambient: Vec3,
+}
+
+impl Default for CloudLayer {
+    fn default() -> Self {
+        CloudLayer {
+            base_height: 25.0,
+            top_height: 40.0,
+            coverage: 0.45,
+            density: 0.12,
+            scale: 0.035,
+            wind: Vec3::new(2.0, 0.0, 0.6),
+        }
+    }
+}
+
+impl CloudLayer {
+    pub fn density_at(&self, point: &Vec3, time: f32) -> f32 {
+        let thickness = self.top_height - self.base_height;
+        let h = ((point.y - self.base_height) / thickness).clamp(0.0, 1.0);
+        // Perfil vertical: base plana y cima redondeada
+        let profile = (h * 4.0).min(1.0) * (1.0 - h).powf(0.7);
+        let sample_point = (point + self.wind * time) * self.scale;
+        let noise = fbm(&sample_point, 4);
+        ((noise - (1.0 - self.coverage)) / self.coverage.max(0.01) * profile).max(0.0) * self.density
+    }
+    
+    // Compone las nubes sobre `background` (radiancia lineal) para un rayo que no tocó la escena
+    pub fn composite(&self, origin: &Vec3, direction: &Vec3, background: Vec3, lighting: &CloudLighting, time: f32) -> Vec3 {
+        if direction.y <= 0.01 {
+            return background;
+        }
+        let t_enter = ((self.base_height - origin.y) / direction.y).max(0.0);
+        let t_exit = (self.top_height - origin.y) / direction.y;
+        if t_exit <= t_enter {
+            return background;
+        }
+        // Rayos casi horizontales recorrerían cientos de unidades: se limita el tramo
+        let length = (t_exit - t_enter).min((self.top_height - self.base_height) * 4.0);
+        let step = length / VIEW_STEPS as f32;
+        // Las nubes dispersan sobre todo hacia delante: brillan al mirar cerca del sol
+        let phase = henyey_greenstein(dot(direction, &lighting.sun_direction), 0.6) * 4.0 * PI;
+        
+        let mut transmittance = 1.0;
+        let mut scattered = Vec3::new(0.0, 0.0, 0.0);
+        for i in 0..VIEW_STEPS {
+            let point = origin + direction * (t_enter + (i as f32 + 0.5) * step);
+            let density = self.density_at(&point, time);
+            if density <= 0.0 {
+                continue;
+            }
+            
+            let light_step = (self.top_height - self.base_height) / LIGHT_STEPS as f32;
+            let mut optical_depth = 0.0;
+            for j in 0..LIGHT_STEPS {
+                let light_point = point + lighting.sun_direction * ((j as f32 + 0.5) * light_step);
+                optical_depth += self.density_at(&light_point, time) * light_step;
+            }
+            let sun_transmittance = (-optical_depth).exp();
+            
+            let absorbed = 1.0 - (-density * step).exp();
+            let light = lighting.sun_color * (sun_transmittance * phase) + lighting.ambient * 0.6;
+            scattered += light * (absorbed * transmittance);
+            transmittance *= 1.0 - absorbed;
+            if transmittance < 0.01 {
+                break;
+            }
+        }
+        background * transmittance + scattered
+    }
+}
@@ -114,4 +114,16 @@ impl Cube {
             (u, v)
         }
     }
+    
+    // Derivadas de la posición respecto a (u, v), coherentes con `get_uv_coordinates`
+    pub fn get_tangent_frame(&self, point: &Vec3) -> (Vec3, Vec3) {
+        let normal = self.get_normal(point);
+        if normal.x != 0.0 {
+            (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, -1.0, 0.0))
+        } else if normal.y != 0.0 {
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))
+        } else {
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0))
+        }
+    }
 }
\ No newline at end of file
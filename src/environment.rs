@@ -0,0 +1,113 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+// Mapa de entorno equirectangular en HDR (radiancia lineal) con muestreo por importancia
+#[derive(Clone)]
+pub struct EnvironmentMap {
+    pub width: usize,
+    pub height: usize,
+    pub intensity: f32,
+    pixels: Vec<Vec3>,
+    marginal_cdf: Vec<f32>,
+    conditional_cdf: Vec<f32>,
+    total_weight: f32,
+}
+
+fn luminance(c: &Vec3) -> f32 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+impl EnvironmentMap {
+    // Archivos Radiance RGBE (.hdr); cualquier otro formato se lee como LDR lineal
+    pub fn load_hdr(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let img = image::open(path)?.to_rgb32f();
+        let (width, height) = img.dimensions();
+        let pixels = img.pixels().map(|p| Vec3::new(p[0], p[1], p[2])).collect();
+        Ok(Self::from_pixels(width as usize, height as usize, pixels))
+    }
+    
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Vec3>) -> Self {
+        // Peso de cada texel: luminancia por sin(theta) para compensar el estiramiento en los polos
+        let mut conditional_cdf = vec![0.0; width * height];
+        let mut marginal_cdf = vec![0.0; height];
+        let mut row_total = 0.0;
+        for y in 0..height {
+            let sin_theta = ((y as f32 + 0.5) / height as f32 * PI).sin();
+            let mut running = 0.0;
+            for x in 0..width {
+                running += luminance(&pixels[y * width + x]).max(0.0) * sin_theta;
+                conditional_cdf[y * width + x] = running;
+            }
+            row_total += running;
+            marginal_cdf[y] = row_total;
+        }
+        
+        EnvironmentMap {
+            width,
+            height,
+            intensity: 1.0,
+            pixels,
+            marginal_cdf,
+            conditional_cdf,
+            total_weight: row_total,
+        }
+    }
+    
+    pub fn direction_to_uv(direction: &Vec3) -> (f32, f32) {
+        let dir = nalgebra_glm::normalize(direction);
+        let u = 0.5 + dir.x.atan2(-dir.z) / (2.0 * PI);
+        let v = dir.y.clamp(-1.0, 1.0).acos() / PI;
+        (u, v)
+    }
+    
+    pub fn uv_to_direction(u: f32, v: f32) -> Vec3 {
+        let phi = (u - 0.5) * 2.0 * PI;
+        let theta = v * PI;
+        Vec3::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos())
+    }
+    
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let (u, v) = Self::direction_to_uv(direction);
+        let x = ((u.rem_euclid(1.0) * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x] * self.intensity
+    }
+    
+    // Elige una dirección con probabilidad proporcional a su brillo; devuelve (dirección, pdf en ángulo sólido)
+    pub fn sample_direction(&self, u1: f32, u2: f32) -> (Vec3, f32) {
+        if self.total_weight <= 0.0 {
+            let dir = Self::uv_to_direction(u1, (1.0 - 2.0 * u2).acos() / PI);
+            return (dir, 1.0 / (4.0 * PI));
+        }
+        
+        let (y, fy) = sample_cdf(&self.marginal_cdf, u1 * self.total_weight);
+        let row = &self.conditional_cdf[y * self.width..(y + 1) * self.width];
+        let row_total = row[self.width - 1];
+        let (x, fx) = sample_cdf(row, u2 * row_total);
+        
+        let u = (x as f32 + fx) / self.width as f32;
+        let v = (y as f32 + fy) / self.height as f32;
+        let direction = Self::uv_to_direction(u, v);
+        (direction, self.pdf_texel(x, y))
+    }
+    
+    fn pdf_texel(&self, x: usize, y: usize) -> f32 {
+        let sin_theta = ((y as f32 + 0.5) / self.height as f32 * PI).sin();
+        if self.total_weight <= 0.0 || sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let row_start = if x == 0 { 0.0 } else { self.conditional_cdf[y * self.width + x - 1] };
+        let weight = self.conditional_cdf[y * self.width + x] - row_start;
+        let probability = weight / self.total_weight;
+        probability * (self.width * self.height) as f32 / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+// Busca el primer índice cuyo acumulado supera `target`; devuelve también la fracción dentro del intervalo
+fn sample_cdf(cdf: &[f32], target: f32) -> (usize, f32) {
+    let index = cdf.partition_point(|&value| value <= target).min(cdf.len() - 1);
+    let start = if index == 0 { 0.0 } else { cdf[index - 1] };
+    let width = cdf[index] - start;
+    let fraction = if width > 0.0 { ((target - start) / width).clamp(0.0, 0.999) } else { 0.5 };
+    (index, fraction)
+}
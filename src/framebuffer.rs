@@ -32,4 +32,36 @@ impl Framebuffer {
             self.buffer[y * self.width + x] = self.current_color.to_hex();
         }
     }
+    
+    // Copia `source` con su esquina superior izquierda en (x0, y0)
+    pub fn blit(&mut self, source: &Framebuffer, x0: usize, y0: usize) {
+        for y in 0..source.height.min(self.height.saturating_sub(y0)) {
+            let width = source.width.min(self.width.saturating_sub(x0));
+            let src = y * source.width;
+            let dst = (y + y0) * self.width + x0;
+            self.buffer[dst..dst + width].copy_from_slice(&source.buffer[src..src + width]);
+        }
+    }
+    
+    // Multiplica por `factor` los píxeles del rectángulo, para que el texto encima se lea sobre cualquier fondo
+    pub fn darken_rect(&mut self, x0: usize, y0: usize, width: usize, height: usize, factor: f32) {
+        for y in y0..(y0 + height).min(self.height) {
+            for x in x0..(x0 + width).min(self.width) {
+                let pixel = self.buffer[y * self.width + x];
+                let channel = |shift: u32| (((pixel >> shift) & 0xFF) as f32 * factor) as u32;
+                self.buffer[y * self.width + x] = (channel(16) << 16) | (channel(8) << 8) | channel(0);
+            }
+        }
+    }
+    
+    pub fn save_png(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut image = image::RgbImage::new(self.width as u32, self.height as u32);
+        for (i, pixel) in self.buffer.iter().enumerate() {
+            let x = (i % self.width) as u32;
+            let y = (i / self.width) as u32;
+            image.put_pixel(x, y, image::Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, *pixel as u8]));
+        }
+        image.save(path)?;
+        Ok(())
+    }
 }
\ No newline at end of file
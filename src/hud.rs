@@ -0,0 +1,51 @@
+use crate::color::Color;
+use crate::font::{GLYPH_HEIGHT, draw_text, text_width};
+use crate::framebuffer::Framebuffer;
+use crate::stats::RenderStats;
+
+const MARGIN: usize = 4;
+const LINE_SPACING: usize = 3;
+
+// Superposición con el rendimiento y el estado de la ventana
+pub struct Hud {
+    pub visible: bool,
+    // Promedio móvil del tiempo por cuadro, para que las cifras no salten cada cuadro
+    pub frame_seconds: f32,
+}
+
+impl Hud {
+    pub fn new(visible: bool) -> Self {
+        Hud { visible, frame_seconds: 0.0 }
+    }
+    
+    pub fn update(&mut self, frame_seconds: f32) {
+        self.frame_seconds = if self.frame_seconds > 0.0 {
+            self.frame_seconds * 0.9 + frame_seconds * 0.1
+        } else {
+            frame_seconds
+        };
+    }
+    
+    // Dibuja el rendimiento y las estadísticas del cuadro seguidos de `lines`, sobre un fondo oscurecido
+    pub fn draw(&self, framebuffer: &mut Framebuffer, stats: &RenderStats, lines: &[String]) {
+        if !self.visible {
+            return;
+        }
+        let fps = if self.frame_seconds > 0.0 { 1.0 / self.frame_seconds } else { 0.0 };
+        let hit_rate = if stats.rays_cast > 0 { stats.hits as f32 / stats.rays_cast as f32 * 100.0 } else { 0.0 };
+        let mut text = vec![
+            format!("FPS {:.1}  FRAME {:.1} MS", fps, self.frame_seconds * 1000.0),
+            format!("RAYS {}  HIT RATE {:.1}%", stats.rays_cast, hit_rate),
+        ];
+        text.extend(lines.iter().cloned());
+
+        let scale = if framebuffer.width >= 800 { 2 } else { 1 };
+        let line_height = (GLYPH_HEIGHT + LINE_SPACING) * scale;
+        let width = text.iter().map(|line| text_width(line, scale)).max().unwrap_or(0) + 2 * MARGIN;
+        let height = text.len() * line_height + 2 * MARGIN - LINE_SPACING * scale;
+        framebuffer.darken_rect(0, 0, width, height, 0.45);
+        for (index, line) in text.iter().enumerate() {
+            draw_text(framebuffer, MARGIN, MARGIN + index * line_height, line, Color::new(235, 235, 235), scale);
+        }
+    }
+}
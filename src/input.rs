@@ -0,0 +1,270 @@
+use minifb::{Key, KeyRepeat, Window};
+
+// Acciones de la ventana; cada una se asocia a una o más teclas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    ZoomIn,
+    ZoomOut,
+    Fast,
+    Reset,
+    CycleCamera,
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    LookLeft,
+    LookRight,
+    LookUp,
+    LookDown,
+    SpeedUp,
+    SpeedDown,
+    FovDown,
+    FovUp,
+    CycleProjection,
+    CycleStereo,
+    CycleFilter,
+    Screenshot,
+    ToggleHud,
+    BreakBlock,
+    PlaceBlock,
+    NextBlock,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 32] = [
+        Action::OrbitLeft, Action::OrbitRight, Action::OrbitUp, Action::OrbitDown, Action::ZoomIn, Action::ZoomOut,
+        Action::Fast, Action::Reset, Action::CycleCamera,
+        Action::MoveForward, Action::MoveBack, Action::MoveLeft, Action::MoveRight, Action::MoveUp, Action::MoveDown,
+        Action::LookLeft, Action::LookRight, Action::LookUp, Action::LookDown, Action::SpeedUp, Action::SpeedDown,
+        Action::FovDown, Action::FovUp, Action::CycleProjection, Action::CycleStereo, Action::CycleFilter,
+        Action::Screenshot, Action::ToggleHud, Action::BreakBlock, Action::PlaceBlock, Action::NextBlock,
+        Action::Quit,
+    ];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::OrbitLeft => "orbit-left",
+            Action::OrbitRight => "orbit-right",
+            Action::OrbitUp => "orbit-up",
+            Action::OrbitDown => "orbit-down",
+            Action::ZoomIn => "zoom-in",
+            Action::ZoomOut => "zoom-out",
+            Action::Fast => "fast",
+            Action::Reset => "reset",
+            Action::CycleCamera => "cycle-camera",
+            Action::MoveForward => "move-forward",
+            Action::MoveBack => "move-back",
+            Action::MoveLeft => "move-left",
+            Action::MoveRight => "move-right",
+            Action::MoveUp => "move-up",
+            Action::MoveDown => "move-down",
+            Action::LookLeft => "look-left",
+            Action::LookRight => "look-right",
+            Action::LookUp => "look-up",
+            Action::LookDown => "look-down",
+            Action::SpeedUp => "speed-up",
+            Action::SpeedDown => "speed-down",
+            Action::FovDown => "fov-down",
+            Action::FovUp => "fov-up",
+            Action::CycleProjection => "cycle-projection",
+            Action::CycleStereo => "cycle-stereo",
+            Action::CycleFilter => "cycle-filter",
+            Action::Screenshot => "screenshot",
+            Action::ToggleHud => "toggle-hud",
+            Action::BreakBlock => "break-block",
+            Action::PlaceBlock => "place-block",
+            Action::NextBlock => "next-block",
+            Action::Quit => "quit",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+    
+    fn default_keys(&self) -> Vec<Key> {
+        match self {
+            Action::OrbitLeft | Action::LookLeft => vec![Key::Left],
+            Action::OrbitRight | Action::LookRight => vec![Key::Right],
+            Action::OrbitUp | Action::LookUp => vec![Key::Up],
+            Action::OrbitDown | Action::LookDown => vec![Key::Down],
+            Action::ZoomIn | Action::MoveForward => vec![Key::W],
+            Action::ZoomOut | Action::MoveBack => vec![Key::S],
+            Action::Fast => vec![Key::LeftShift, Key::RightShift],
+            Action::Reset => vec![Key::Space],
+            Action::CycleCamera => vec![Key::C],
+            Action::MoveLeft => vec![Key::A],
+            Action::MoveRight => vec![Key::D],
+            Action::MoveUp => vec![Key::E],
+            Action::MoveDown => vec![Key::Q],
+            Action::SpeedUp => vec![Key::Equal, Key::NumPadPlus],
+            Action::SpeedDown => vec![Key::Minus, Key::NumPadMinus],
+            Action::FovDown => vec![Key::LeftBracket],
+            Action::FovUp => vec![Key::RightBracket],
+            Action::CycleProjection => vec![Key::P],
+            Action::CycleStereo => vec![Key::V],
+            Action::CycleFilter => vec![Key::F],
+            Action::Screenshot => vec![Key::F12],
+            Action::ToggleHud => vec![Key::H],
+            Action::BreakBlock => vec![Key::X, Key::Delete],
+            Action::PlaceBlock => vec![Key::B, Key::Insert],
+            Action::NextBlock => vec![Key::M],
+            Action::Quit => vec![Key::Escape],
+        }
+    }
+}
+
+// Teclas que se pueden nombrar en el archivo de controles, con el mismo nombre que en minifb
+const KEYS: &[Key] = &[
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Down, Key::Left, Key::Right, Key::Up,
+    Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma, Key::Equal, Key::LeftBracket, Key::Minus,
+    Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+    Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape, Key::Home, Key::Insert, Key::PageDown,
+    Key::PageUp, Key::Space, Key::Tab,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl, Key::LeftAlt, Key::RightAlt,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6,
+    Key::NumPad7, Key::NumPad8, Key::NumPad9, Key::NumPadPlus, Key::NumPadMinus,
+];
+
+// Acepta el nombre de minifb sin distinguir mayúsculas; los dígitos pueden escribirse solos ("1" = Key1)
+pub fn parse_key(name: &str) -> Option<Key> {
+    let name = if name.len() == 1 && name.chars().all(|c| c.is_ascii_digit()) { format!("Key{}", name) } else { name.to_string() };
+    KEYS.iter().copied().find(|key| format!("{:?}", key).eq_ignore_ascii_case(&name))
+}
+
+// Mapa de acciones a teclas, con los controles por defecto salvo lo que cambie el archivo
+pub struct InputMap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        InputMap { bindings: Action::ALL.iter().map(|action| (*action, action.default_keys())).collect() }
+    }
+}
+
+impl InputMap {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(path, &text)
+    }
+    
+    // Una línea por acción: `accion = Tecla[, Tecla...]`; `#` inicia un comentario y las acciones
+    // que no aparecen conservan sus teclas por defecto. Una misma acción solo puede aparecer una vez
+    pub fn parse(path: &str, text: &str) -> Result<Self, String> {
+        let mut map = InputMap::default();
+        let mut seen: Vec<(Action, usize)> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, keys) = line.split_once('=')
+                .ok_or_else(|| format!("{}:{}: expected 'action = key'", path, number + 1))?;
+            let action = Action::from_name(name.trim())
+                .ok_or_else(|| format!("{}:{}: unknown action '{}'", path, number + 1, name.trim()))?;
+            if let Some((_, first)) = seen.iter().find(|(bound, _)| *bound == action) {
+                return Err(format!("{}:{}: '{}' is already bound on line {}", path, number + 1, action.name(), first));
+            }
+            seen.push((action, number + 1));
+            let keys = keys.split(',').map(str::trim).filter(|key| !key.is_empty())
+                .map(|key| parse_key(key).ok_or_else(|| format!("{}:{}: unknown key '{}'", path, number + 1, key)))
+                .collect::<Result<Vec<_>, _>>()?;
+            map.bind(action, keys);
+        }
+        Ok(map)
+    }
+    
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+            binding.1 = keys;
+        }
+    }
+    
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings.iter().find(|(bound, _)| *bound == action).map_or(&[], |(_, keys)| keys.as_slice())
+    }
+    
+    pub fn is_down(&self, window: &Window, action: Action) -> bool {
+        self.keys(action).iter().any(|key| window.is_key_down(*key))
+    }
+    
+    pub fn is_pressed(&self, window: &Window, action: Action, repeat: KeyRepeat) -> bool {
+        self.keys(action).iter().any(|key| window.is_key_pressed(*key, repeat))
+    }
+    
+    // -1, 0 o 1 según cuál de las dos acciones esté pulsada
+    pub fn axis(&self, window: &Window, positive: Action, negative: Action) -> f32 {
+        self.is_down(window, positive) as i32 as f32 - self.is_down(window, negative) as i32 as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn comments_and_blank_lines_are_ignored() {
+        let text = "# controles\n\norbit-left = A, Left  # también las flechas\n   # solo un comentario\n";
+        let map = InputMap::parse("controls.txt", text).unwrap();
+        assert_eq!(map.keys(Action::OrbitLeft), &[Key::A, Key::Left]);
+        assert_eq!(map.keys(Action::OrbitRight), &[Key::Right]);
+    }
+    
+    #[test]
+    fn key_names_ignore_case_and_accept_bare_digits() {
+        let map = InputMap::parse("controls.txt", "screenshot = f5, 1\nfast = leftshift").unwrap();
+        assert_eq!(map.keys(Action::Screenshot), &[Key::F5, Key::Key1]);
+        assert_eq!(map.keys(Action::Fast), &[Key::LeftShift]);
+    }
+    
+    #[test]
+    fn unknown_action_is_reported_with_its_line() {
+        let error = InputMap::parse("controls.txt", "quit = Escape\njump = Space").err().unwrap();
+        assert_eq!(error, "controls.txt:2: unknown action 'jump'");
+    }
+    
+    #[test]
+    fn unknown_key_is_reported_with_its_line() {
+        let error = InputMap::parse("controls.txt", "\nquit = Escape, Esc").err().unwrap();
+        assert_eq!(error, "controls.txt:2: unknown key 'Esc'");
+    }
+    
+    #[test]
+    fn line_without_equals_is_rejected() {
+        let error = InputMap::parse("controls.txt", "quit Escape").err().unwrap();
+        assert_eq!(error, "controls.txt:1: expected 'action = key'");
+    }
+    
+    #[test]
+    fn action_bound_twice_is_rejected() {
+        let error = InputMap::parse("controls.txt", "quit = Escape\n# otra vez\nquit = Q").err().unwrap();
+        assert_eq!(error, "controls.txt:3: 'quit' is already bound on line 1");
+    }
+    
+    #[test]
+    fn one_key_can_serve_several_actions() {
+        let map = InputMap::parse("controls.txt", "zoom-in = Z\nmove-forward = Z").unwrap();
+        assert_eq!(map.keys(Action::ZoomIn), &[Key::Z]);
+        assert_eq!(map.keys(Action::MoveForward), &[Key::Z]);
+    }
+    
+    #[test]
+    fn shipped_controls_file_matches_the_defaults() {
+        let map = InputMap::parse("controls.txt", include_str!("../controls.txt")).unwrap();
+        for action in Action::ALL {
+            assert_eq!(map.keys(action), action.default_keys().as_slice(), "{}", action.name());
+        }
+    }
+}
@@ -4,410 +4,304 @@ mod cube;
 mod camera;
 mod material;
 mod stats;
+mod texture;
+mod cli;
+mod sampling;
+mod environment;
+mod sky;
+mod skybox;
+mod camera_path;
+mod session;
+mod noise;
+mod clouds;
+mod media;
+mod input;
+mod screenshot;
+mod font;
+mod hud;
 
 use framebuffer::Framebuffer;
 use color::Color;
 use cube::Cube;
-use camera::OrbitCamera;
-use material::{Material, MaterialType};
+use camera::{Camera, CameraMode, FlyCamera, OrbitCamera, Projection, ProjectionKind, SensorFit, StereoEye, StereoLayout, 
+             StereoSettings, ViewPreset, cube_face_cameras};
+use material::{AlphaMode, Material, MaterialType};
 use stats::RenderStats;
+use texture::{SurfaceFrame, TextureFilter, TextureRegistry};
+use cli::{CliOptions, USAGE};
+use sampling::{Rng, sample_cosine_power, sample_disk};
+use environment::EnvironmentMap;
+use sky::PreethamSky;
+use skybox::Skybox;
+use camera_path::{CameraPath, PathCamera};
+use session::{BlockEdit, SessionFrame, SessionRecorder};
+use clouds::{CloudLayer, CloudLighting};
+use media::{Fog, FogShape, HeterogeneousMedium, henyey_greenstein};
 use nalgebra_glm::{Vec3, normalize, dot};
-use minifb::{Key, Window, WindowOptions};
-use image::open;
+use input::{Action, InputMap};
+use hud::Hud;
+use screenshot::{ScreenshotCamera, Screenshots};
+use minifb::{KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use std::sync::Arc;
+use std::time::Instant;
 use std::f32::consts::PI;
 
 const WIDTH: usize = 400;
 const HEIGHT: usize = 300;
 const MAX_DEPTH: u32 = 3;
+// Límite de radiancia por muestra brillante del entorno, evita puntos blancos aislados con pocas muestras
+const IBL_SAMPLE_CLAMP: f32 = 4.0;
+// Fracción del color de la niebla que se mantiene como luz ambiente cuando se calculan haces de luz
+const FOG_AMBIENT_SCATTER: f32 = 0.35;
+// Grosor del contorno del bloque seleccionado, en fracción de la cara
+const SELECTION_EDGE: f32 = 0.05;
+// Texeles recortados que puede cruzar un rayo antes de darse por perdido
+const MAX_CUTOUT_CROSSINGS: u32 = 32;
+// Altura del humo sobre la lava, en bloques
+const SMOKE_HEIGHT: f32 = 3.5;
 
+#[derive(Clone)]
 pub struct Light {
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    // Si está definida, la luz es direccional (como el sol) y apunta desde la superficie hacia la luz
+    pub direction: Option<Vec3>,
 }
 
 impl Light {
     pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
-        Light { position, color, intensity }
-    }
-}
-
-pub struct Plane {
-    pub point: Vec3,
-    pub normal: Vec3,
-    pub material: Material,
-}
-
-impl Plane {
-    pub fn new(point: Vec3, normal: Vec3, material: Material) -> Self {
-        Plane {
-            point,
-            normal: nalgebra_glm::normalize(&normal),
-            material,
-        }
+        Light { position, color, intensity, direction: None }
     }
     
-    pub fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
-        let denom = nalgebra_glm::dot(&self.normal, ray_direction);
-        if denom.abs() < 1e-6 { return None; }
-        let t = nalgebra_glm::dot(&(self.point - ray_origin), &self.normal) / denom;
-        if t > 0.001 { Some(t) } else { None }
+    pub fn directional(direction: Vec3, color: Color, intensity: f32) -> Self {
+        Light { position: Vec3::new(0.0, 0.0, 0.0), color, intensity, direction: Some(normalize(&direction)) }
     }
     
-    pub fn get_normal(&self, _point: &Vec3) -> Vec3 {
-        self.normal
+    // Dirección hacia la luz y distancia desde `point` (infinita para luces direccionales)
+    pub fn direction_from(&self, point: &Vec3) -> (Vec3, f32) {
+        match self.direction {
+            Some(direction) => (direction, f32::INFINITY),
+            None => (normalize(&(self.position - point)), nalgebra_glm::distance(&self.position, point)),
+        }
     }
 }
 
+// Todo lo que necesita un rayo para resolverse, compartido por la ventana y el render offline; las imágenes
+// van detrás de `Arc` para que copiar la escena (capturas en otro hilo) no duplique texturas ni cielos
 #[derive(Clone)]
-pub struct Texture {
-    pub width: u32,
-    pub height: u32,
-    pub data: Vec<u8>,
+pub struct Scene {
+    pub diorama: OptimizedDiorama,
+    pub floor: Plane,
+    pub lights: Vec<Light>,
+    pub textures: Arc<TextureRegistry>,
+    // Siempre hay uno: si no se puede cargar se usa el cielo procedural de seis caras
+    pub skybox: Arc<Skybox>,
+    pub environment: Option<Arc<EnvironmentMap>>,
+    pub ibl_samples: u32,
+    pub pixel_samples: u32,
+    // Tiempo que el obturador permanece abierto en cada cuadro (0 = sin desenfoque de movimiento)
+    pub shutter: f32,
+    pub sky: Option<PreethamSky>,
+    pub clouds: Option<CloudLayer>,
+    pub fog: Option<Fog>,
+    pub media: Vec<HeterogeneousMedium>,
+    // Con el humo activado se vuelve a colocar sobre la lava cada vez que se edita un bloque
+    pub lava_smoke: bool,
+    // Bloque elegido con el ratón en la ventana (índice en `diorama.cubes`, normal de la cara)
+    pub selection: Option<(usize, Vec3)>,
 }
 
-impl Texture {
-    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let img = open(path)?;
-        let rgb_img = img.to_rgb8();
-        let (width, height) = rgb_img.dimensions();
-        let data = rgb_img.into_raw();
-        Ok(Texture { width, height, data })
-    }
-    
-    pub fn sample(&self, u: f32, v: f32) -> Color {
-        let u = (u.fract() + 1.0).fract().clamp(0.0, 1.0);
-        let v = (v.fract() + 1.0).fract().clamp(0.0, 1.0);
-        let x = ((u * self.width as f32) as u32).min(self.width - 1);
-        let y = ((v * self.height as f32) as u32).min(self.height - 1);
-        let index = ((y * self.width + x) * 3) as usize;
-        
-        if index + 2 < self.data.len() {
-            Color::new(self.data[index], self.data[index + 1], self.data[index + 2])
-        } else {
-            Color::new(255, 0, 255)
-        }
-    }
-    
-    pub fn create_grass_texture() -> Self {
-        let size = 32;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let noise1 = ((x * 17 + y * 13) % 16) as f32 / 16.0;
-                let noise2 = ((x * 7 + y * 11) % 8) as f32 / 8.0;
-                let combined_noise = (noise1 + noise2 * 0.3).clamp(0.0, 1.0);
-                let base_green = 160 + (combined_noise * 60.0) as u8;
-                let r = (25.0 + combined_noise * 35.0) as u8;
-                let b = (25.0 + combined_noise * 30.0) as u8;
-                data.extend_from_slice(&[r, base_green, b]);
-            }
-        }
-        Texture { width: 32, height: 32, data }
-    }
-    
-    pub fn create_stone_texture() -> Self {
-        let size = 32;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let noise1 = ((x * 23 + y * 19) % 32) as f32 / 32.0;
-                let noise2 = ((x * 7 + y * 13) % 16) as f32 / 16.0;
-                let combined_noise = (noise1 + noise2 * 0.4).clamp(0.0, 1.0);
-                let base_gray = (70.0 + combined_noise * 40.0) as u8;
-                let variation = (combined_noise * 15.0) as u8;
-                data.extend_from_slice(&[ base_gray + variation, base_gray + (variation / 2), base_gray ]);
-            }
-        }
-        Texture { width: 32, height: 32, data }
-    }
-    
-    pub fn create_dirt_texture() -> Self {
-        let size = 32;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let noise1 = ((x * 13 + y * 17) % 24) as f32 / 24.0;
-                let noise2 = ((x * 29 + y * 7) % 16) as f32 / 16.0;
-                let combined_noise = (noise1 + noise2 * 0.5).clamp(0.0, 1.0);
-                let brown_r = (140.0 + combined_noise * 50.0) as u8;
-                let brown_g = (85.0 + combined_noise * 35.0) as u8;
-                let brown_b = (35.0 + combined_noise * 25.0) as u8;
-                data.extend_from_slice(&[brown_r, brown_g, brown_b]);
-            }
-        }
-        Texture { width: 32, height: 32, data }
-    }
-    
-    pub fn create_water_texture() -> Self {
-        let size = 32;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let wave1 = ((x as f32 * 0.4).sin() + (y as f32 * 0.3).sin()) * 0.3;
-                let wave2 = ((x as f32 * 0.2 + y as f32 * 0.2).sin()) * 0.2;
-                let wave_intensity = (wave1 + wave2).clamp(-0.5, 0.5);
-                let base_blue = 40 + (wave_intensity * 20.0) as i32;
-                let base_green = 140 + (wave_intensity * 30.0) as i32;
-                let base_alpha = 200;
-                data.extend_from_slice(&[ base_blue.clamp(20,80) as u8, base_green.clamp(120,180) as u8, base_alpha as u8 ]);
-            }
-        }
-        Texture { width: 32, height: 32, data }
-    }
-    
-    pub fn create_lava_texture() -> Self {
-        let size = 32;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let noise1 = ((x * 31 + y * 17) % 32) as f32 / 32.0;
-                let noise2 = ((x * 13 + y * 29) % 16) as f32 / 16.0;
-                let intensity = (noise1 + noise2 * 0.6).clamp(0.0, 1.0);
-                if intensity > 0.7 {
-                    data.extend_from_slice(&[255, 255, (150.0 + intensity * 105.0) as u8]);
-                } else if intensity > 0.4 {
-                    data.extend_from_slice(&[255, (120.0 + intensity * 135.0) as u8, 30]);
-                } else {
-                    data.extend_from_slice(&[(180.0 + intensity * 75.0) as u8, 20, 0]);
-                }
+// Estado por rayo primario: tiempo global, apertura del píxel y generador aleatorio
+pub struct RayContext {
+    pub time: f32,
+    pub pixel_spread: f32,
+    // Rayos paralelos: `pixel_spread` ya es un ancho en el mundo y no crece con la distancia
+    pub orthographic: bool,
+    pub rng: Rng,
+}
+
+impl Scene {
+    pub fn load(options: &CliOptions) -> Self {
+        let mut textures = TextureRegistry::load_default();
+        textures.filter = options.texture_filter;
+
+        let environment = options.hdri.as_ref().and_then(|path| match EnvironmentMap::load_hdr(path) {
+            Ok(mut env) => {
+                println!("Environment map loaded from {} ({}x{})", path, env.width, env.height);
+                env.intensity = options.hdri_intensity;
+                Some(env)
             }
-        }
-        Texture { width: 32, height: 32, data }
-    }
-    
-    pub fn create_obsidian_texture() -> Self {
-        let size = 32;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let noise1 = ((x * 43 + y * 23) % 16) as f32 / 16.0;
-                let noise2 = ((x * 17 + y * 31) % 8) as f32 / 8.0;
-                let combined_noise = (noise1 + noise2 * 0.2).clamp(0.0, 1.0);
-                
-                let base_intensity = 15.0 + combined_noise * 25.0;
-                let purple_tint = if combined_noise > 0.8 { 20.0 } else { 5.0 };
-                
-                let r = (base_intensity + purple_tint * 0.6) as u8;
-                let g = base_intensity as u8;
-                let b = (base_intensity + purple_tint) as u8;
-                
-                data.extend_from_slice(&[r, g, b]);
+            Err(e) => {
+                println!("Failed to load environment map {}: {}", path, e);
+                None
             }
-        }
-        Texture { width: 32, height: 32, data }
-    }
-}
+        });
 
-#[derive(Clone)]
-pub struct Skybox {
-    pub px: Texture,
-    pub nx: Texture,
-    pub py: Texture,
-    pub ny: Texture,
-    pub pz: Texture,
-    pub nz: Texture,
-}
-
-impl Skybox {
-    pub fn create_procedural_sky() -> Self {
-        Skybox {
-            px: Self::create_sky_texture_right(),
-            nx: Self::create_sky_texture_left(), 
-            py: Self::create_sky_texture_top(),
-            ny: Self::create_sky_texture_bottom(),
-            pz: Self::create_sky_texture_front(),
-            nz: Self::create_sky_texture_back(),
-        }
-    }
-
-    pub fn load_from_files() -> Result<Self, Box<dyn std::error::Error>> {
-        match Self::try_load_from_files() {
-            Ok(skybox) => {
-                println!("Skybox loaded from files successfully!");
-                Ok(skybox)
-            },
+        let mut skybox = match Skybox::load(&options.skybox_path, options.skybox_layout) {
+            Ok(sb) => {
+                println!("Skybox loaded from {}", options.skybox_path);
+                sb
+            }
             Err(e) => {
-                println!("Failed to load skybox files: {}", e);
+                println!("Failed to load skybox {}: {}", options.skybox_path, e);
                 println!("Using procedural skybox instead...");
-                Ok(Self::create_procedural_sky())
+                Skybox::create_procedural_sky()
             }
+        };
+        skybox.options = options.skybox_options;
+
+        let diorama = OptimizedDiorama::new(Vec3::new(0.0, 0.0, 0.0), 0.8, options.decorations);
+        let floor = Plane::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Material::stone_wall());
+
+        let mut lights = vec![
+            Light::new(Vec3::new(-4.0, 8.0, -2.0), Color::new(255, 220, 180), 1.3),
+            Light::new(Vec3::new(6.0, 6.0, 3.0), Color::new(180, 200, 255), 0.9),
+        ];
+
+        // El cielo físico sustituye la luz principal por un sol direccional alineado con el disco
+        let sky = if options.physical_sky {
+            let sun_direction = PreethamSky::sun_direction_from_angles(options.sun_elevation, options.sun_azimuth);
+            let sky = PreethamSky::new(sun_direction, options.turbidity);
+            let sun = sky.sun_color();
+            lights[0] = Light::directional(sun_direction, Color::from_float(sun.x, sun.y, sun.z), 1.3);
+            Some(sky)
+        } else {
+            None
+        };
+
+        let clouds = if options.clouds {
+            let defaults = CloudLayer::default();
+            Some(CloudLayer {
+                coverage: options.cloud_coverage.clamp(0.0, 1.0),
+                wind: defaults.wind * options.wind_speed,
+                ..defaults
+            })
+        } else {
+            None
+        };
+
+        let fog = if options.fog_density > 0.0 {
+            let shape = match options.fog_height {
+                Some(base_height) => FogShape::Height { base_height, falloff: options.fog_falloff },
+                None => FogShape::Uniform,
+            };
+            let mut fog = Fog::new(shape, options.fog_density, options.fog_color.to_vec3() / 255.0);
+            fog.light_shafts = options.light_shafts;
+            Some(fog)
+        } else {
+            None
+        };
+
+        let media = Self::lava_media(options.lava_smoke, diorama.lava_pool);
+
+        Scene {
+            diorama, floor, lights,
+            textures: Arc::new(textures),
+            skybox: Arc::new(skybox),
+            environment: environment.map(Arc::new),
+            ibl_samples: options.ibl_samples,
+            pixel_samples: options.samples.max(1),
+            shutter: options.shutter / options.fps,
+            sky, clouds, fog, media,
+            lava_smoke: options.lava_smoke,
+            selection: None,
         }
     }
     
-    fn try_load_from_files() -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Skybox {
-            px: Texture::load_from_file("px.png")?,
-            nx: Texture::load_from_file("nx.png")?,
-            py: Texture::load_from_file("py.png")?,
-            ny: Texture::load_from_file("ny.png")?,
-            pz: Texture::load_from_file("pz.png")?,
-            nz: Texture::load_from_file("nz.png")?,
-        })
+    fn lava_media(lava_smoke: bool, lava_pool: Option<(Vec3, Vec3)>) -> Vec<HeterogeneousMedium> {
+        match (lava_smoke, lava_pool) {
+            (true, Some((min, max))) => vec![HeterogeneousMedium::lava_smoke(min, max)],
+            _ => Vec::new(),
+        }
     }
     
-    fn create_sky_texture_top() -> Texture {
-        let size = 256;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let cloud_noise = ((x * 7 + y * 13) % 64) as f32 / 64.0;
-                let cloud_intensity = if cloud_noise > 0.7 { 0.8 } else { 0.2 };
-                
-                let base_r = (130.0 + cloud_intensity * 100.0) as u8;
-                let base_g = (180.0 + cloud_intensity * 50.0) as u8;
-                let base_b = 255;
-                
-                data.extend_from_slice(&[base_r, base_g, base_b]);
+    // Aplica un cambio del editor de bloques y mueve el humo con el estanque; false si no se pudo hacer
+    pub fn apply_edit(&mut self, edit: &BlockEdit) -> bool {
+        let applied = match *edit {
+            BlockEdit::Break(index) if index < self.diorama.cubes.len() => {
+                self.diorama.remove_block(index);
+                true
+            }
+            BlockEdit::Place(index, normal, material) if index < self.diorama.cubes.len() => {
+                self.diorama.place_block(index, normal, material)
             }
+            _ => false,
+        };
+        if applied {
+            self.media = Self::lava_media(self.lava_smoke, self.diorama.lava_pool);
         }
-        Texture { width: size, height: size, data }
+        applied
     }
     
-    fn create_sky_texture_bottom() -> Texture {
-        let size = 256;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let fade = y as f32 / size as f32;
-                let r = (80.0 + fade * 50.0) as u8;
-                let g = (120.0 + fade * 60.0) as u8;
-                let b = (200.0 + fade * 55.0) as u8;
-                
-                data.extend_from_slice(&[r, g, b]);
-            }
-        }
-        Texture { width: size, height: size, data }
+    // Si una captura en curso comparte las texturas se copian antes de cambiar el filtro
+    pub fn set_texture_filter(&mut self, filter: TextureFilter) {
+        Arc::make_mut(&mut self.textures).filter = filter;
     }
     
-    fn create_sky_texture_front() -> Texture {
-        let size = 256;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let fade = (size - y) as f32 / size as f32;
-                let sun_x = size / 4;
-                let sun_y = size / 3;
-                let dist = ((x as f32 - sun_x as f32).powi(2) + (y as f32 - sun_y as f32).powi(2)).sqrt();
-                let sun_effect = if dist < 30.0 { 0.8 } else { 0.0 };
-                
-                let r = (100.0 + fade * 80.0 + sun_effect * 100.0) as u8;
-                let g = (150.0 + fade * 80.0 + sun_effect * 80.0) as u8;
-                let b = (220.0 + fade * 35.0) as u8;
-                
-                data.extend_from_slice(&[r, g, b]);
-            }
+    // Dirección y color (0..1) de la luz principal, usada como sol por las nubes
+    pub fn sun(&self) -> (Vec3, Vec3) {
+        let light = &self.lights[0];
+        let direction = light.direction.unwrap_or_else(|| normalize(&light.position));
+        (direction, light.color.to_vec3() * (light.intensity / 255.0))
+    }
+}
+
+#[derive(Clone)]
+pub struct Plane {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub material: Material,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Material) -> Self {
+        Plane {
+            point,
+            normal: nalgebra_glm::normalize(&normal),
+            material,
         }
-        Texture { width: size, height: size, data }
     }
     
-    fn create_sky_texture_back() -> Texture {
-        let size = 256;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let fade = (size - y) as f32 / size as f32;
-                let r = (90.0 + fade * 60.0) as u8;
-                let g = (140.0 + fade * 70.0) as u8;
-                let b = (210.0 + fade * 45.0) as u8;
-                
-                data.extend_from_slice(&[r, g, b]);
-            }
-        }
-        Texture { width: size, height: size, data }
+    pub fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+        let denom = nalgebra_glm::dot(&self.normal, ray_direction);
+        if denom.abs() < 1e-6 { return None; }
+        let t = nalgebra_glm::dot(&(self.point - ray_origin), &self.normal) / denom;
+        if t > 0.001 { Some(t) } else { None }
     }
     
-    fn create_sky_texture_left() -> Texture {
-        let size = 256;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let fade = (size - y) as f32 / size as f32;
-                let r = (95.0 + fade * 65.0) as u8;
-                let g = (145.0 + fade * 75.0) as u8;
-                let b = (215.0 + fade * 40.0) as u8;
-                
-                data.extend_from_slice(&[r, g, b]);
-            }
-        }
-        Texture { width: size, height: size, data }
+    pub fn get_normal(&self, _point: &Vec3) -> Vec3 {
+        self.normal
     }
     
-    fn create_sky_texture_right() -> Texture {
-        let size = 256;
-        let mut data = Vec::with_capacity((size * size * 3) as usize);
-        for y in 0..size {
-            for x in 0..size {
-                let fade = (size - y) as f32 / size as f32;
-                let r = (105.0 + fade * 55.0) as u8;
-                let g = (155.0 + fade * 65.0) as u8;
-                let b = (225.0 + fade * 30.0) as u8;
-                
-                data.extend_from_slice(&[r, g, b]);
-            }
-        }
-        Texture { width: size, height: size, data }
+    pub fn get_tangent_frame(&self) -> (Vec3, Vec3) {
+        let helper = if self.normal.y.abs() < 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let tangent = nalgebra_glm::normalize(&nalgebra_glm::cross(&helper, &self.normal));
+        let bitangent = nalgebra_glm::cross(&self.normal, &tangent);
+        (tangent, bitangent)
     }
     
-    pub fn sample(&self, direction: &Vec3) -> Color {
-        let dir = nalgebra_glm::normalize(direction);
-        
-        let adjusted_dir = Vec3::new(
-            dir.x * 1.2,
-            dir.y * 0.8,
-            dir.z * 1.2
-        ).normalize();
-        
-        let abs_x = adjusted_dir.x.abs();
-        let abs_y = adjusted_dir.y.abs();
-        let abs_z = adjusted_dir.z.abs();
-        
-        let (texture, u, v) = if abs_x >= abs_y && abs_x >= abs_z {
-            if adjusted_dir.x > 0.0 {
-                let u = (-adjusted_dir.z / abs_x + 1.0) * 0.5;
-                let v = (-adjusted_dir.y / abs_x + 1.0) * 0.5;
-                (&self.px, u, v)
-            } else {
-                let u = (adjusted_dir.z / abs_x + 1.0) * 0.5;
-                let v = (-adjusted_dir.y / abs_x + 1.0) * 0.5;
-                (&self.nx, u, v)
-            }
-        } else if abs_y >= abs_x && abs_y >= abs_z {
-            if adjusted_dir.y > 0.0 {
-                let u = (adjusted_dir.x / abs_y + 1.0) * 0.5;
-                let v = (adjusted_dir.z / abs_y + 1.0) * 0.5;
-                (&self.py, u, v)
-            } else {
-                let u = (adjusted_dir.x / abs_y + 1.0) * 0.5;
-                let v = (-adjusted_dir.z / abs_y + 1.0) * 0.5;
-                (&self.ny, u, v)
-            }
-        } else {
-            if adjusted_dir.z > 0.0 {
-                let u = (adjusted_dir.x / abs_z + 1.0) * 0.5;
-                let v = (-adjusted_dir.y / abs_z + 1.0) * 0.5;
-                (&self.pz, u, v)
-            } else {
-                let u = (-adjusted_dir.x / abs_z + 1.0) * 0.5;
-                let v = (-adjusted_dir.y / abs_z + 1.0) * 0.5;
-                (&self.nz, u, v)
-            }
-        };
-        
-        texture.sample(u, v)
+    // Proyección planar: una repetición de la textura por unidad de mundo
+    pub fn get_uv_coordinates(&self, point: &Vec3) -> (f32, f32) {
+        let (tangent, bitangent) = self.get_tangent_frame();
+        let local = point - self.point;
+        (dot(&local, &tangent), dot(&local, &bitangent))
     }
 }
 
+#[derive(Clone)]
 pub struct OptimizedDiorama {
     pub cubes: Vec<Cube>,
     pub water_planes: Vec<Plane>,
     pub lava_planes: Vec<Plane>,
+    // Aire sobre la lava al descubierto, donde sube el humo; None si toda la lava está tapada
+    pub lava_pool: Option<(Vec3, Vec3)>,
     pub bounding_box_min: Vec3,
     pub bounding_box_max: Vec3,
 }
 
 impl OptimizedDiorama {
-    pub fn new(center: Vec3, cube_size: f32) -> Self {
+    pub fn new(center: Vec3, cube_size: f32, decorations: bool) -> Self {
         let mut cubes = Vec::new();
         let mut water_planes = Vec::new();
         let mut lava_planes = Vec::new();
@@ -444,13 +338,19 @@ impl OptimizedDiorama {
             }
         }
         
+        if decorations {
+            Self::add_decorations(&mut cubes, &terrain_heights, center, cube_size, spacing, offset);
+        }
         Self::add_water_areas(&mut water_planes, &terrain_heights, center, cube_size, spacing, offset);
         Self::add_lava_areas(&mut lava_planes, &terrain_heights, center, cube_size, spacing, offset);
         
+        let lava_pool = Self::find_lava_pool(&cubes);
+        
         OptimizedDiorama { 
             cubes, 
             water_planes, 
             lava_planes,
+            lava_pool,
             bounding_box_min: min_pos - Vec3::new(2.0, 2.0, 2.0),
             bounding_box_max: max_pos + Vec3::new(2.0, 2.0, 2.0),
         }
@@ -606,6 +506,33 @@ impl OptimizedDiorama {
         true
     }
     
+    // Árbol, vidrieras y una reja sobre la escalera de césped
+    fn add_decorations(cubes: &mut Vec<Cube>, heights: &[Vec<usize>], center: Vec3, cube_size: f32, spacing: f32, offset: f32) {
+        let grid_position = |x: usize, z: usize, y_level: usize| Vec3::new(
+            center.x + x as f32 * spacing - offset,
+            center.y + (y_level as f32) * spacing,
+            center.z + z as f32 * spacing - offset,
+        );
+        
+        let trunk_base = heights[10][10] + 1;
+        for y_level in trunk_base..trunk_base + 2 {
+            cubes.push(Cube::new(grid_position(10, 10, y_level), cube_size, Material::wood_planks()));
+        }
+        for z in 9..=11 {
+            for x in 9..=11 {
+                cubes.push(Cube::new(grid_position(x, z, trunk_base + 2), cube_size, Material::leaves_block()));
+            }
+        }
+        
+        for x in [9, 11] {
+            cubes.push(Cube::new(grid_position(x, 7, heights[7][x] + 1), cube_size, Material::stained_glass()));
+        }
+        
+        for x in 9..=11 {
+            cubes.push(Cube::new(grid_position(x, 4, heights[4][x] + 1), cube_size, Material::grate_block()));
+        }
+    }
+    
     fn add_water_areas(_water_planes: &mut Vec<Plane>, _heights: &Vec<Vec<usize>>, _center: Vec3, _cube_size: f32, _spacing: f32, _offset: f32) {
     }
     
@@ -633,6 +560,59 @@ impl OptimizedDiorama {
         closest_index.map(|idx| (idx, closest_distance, 1))
     }
     
+    // Bloque bajo el rayo y normal de la cara que toca
+    pub fn pick(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(usize, Vec3)> {
+        let (index, distance, _) = self.ray_intersect_fast(ray_origin, ray_direction)?;
+        let point = ray_origin + ray_direction * distance;
+        Some((index, self.cubes[index].get_normal(&point)))
+    }
+    
+    pub fn remove_block(&mut self, index: usize) -> Cube {
+        let cube = self.cubes.remove(index);
+        self.lava_pool = Self::find_lava_pool(&self.cubes);
+        cube
+    }
+    
+    // Coloca un bloque del mismo tamaño pegado a la cara `normal` del bloque `index`; devuelve false si
+    // el hueco ya está ocupado
+    pub fn place_block(&mut self, index: usize, normal: Vec3, material: Material) -> bool {
+        let neighbor = &self.cubes[index];
+        let size = neighbor.max.x - neighbor.min.x;
+        let center = (neighbor.min + neighbor.max) * 0.5 + normal * size;
+        let occupied = self.cubes.iter().any(|cube| {
+            (0..3).all(|i| center[i] > cube.min[i] && center[i] < cube.max[i])
+        });
+        if occupied {
+            return false;
+        }
+        let cube = Cube::new(center, size, material);
+        self.bounding_box_min = nalgebra_glm::min2(&self.bounding_box_min, &cube.min);
+        self.bounding_box_max = nalgebra_glm::max2(&self.bounding_box_max, &cube.max);
+        self.cubes.push(cube);
+        self.lava_pool = Self::find_lava_pool(&self.cubes);
+        true
+    }
+    
+    // Caja sobre los bloques de lava que no tienen otro bloque encima, con la altura que alcanza el humo
+    fn find_lava_pool(cubes: &[Cube]) -> Option<(Vec3, Vec3)> {
+        let is_filled = |point: &Vec3| cubes.iter().any(|cube| (0..3).all(|i| point[i] > cube.min[i] && point[i] < cube.max[i]));
+        let mut pool: Option<(Vec3, Vec3)> = None;
+        for cube in cubes.iter().filter(|cube| cube.material.material_type == MaterialType::Lava) {
+            let size = cube.max.y - cube.min.y;
+            let above = (cube.min + cube.max) * 0.5 + Vec3::new(0.0, size, 0.0);
+            if is_filled(&above) {
+                continue;
+            }
+            let min = Vec3::new(cube.min.x, cube.max.y, cube.min.z);
+            let max = Vec3::new(cube.max.x, cube.max.y + size * SMOKE_HEIGHT, cube.max.z);
+            pool = Some(match pool {
+                Some((pool_min, pool_max)) => (nalgebra_glm::min2(&pool_min, &min), nalgebra_glm::max2(&pool_max, &max)),
+                None => (min, max),
+            });
+        }
+        pool
+    }
+    
     fn ray_intersects_bbox(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> bool {
         let mut t_min = f32::NEG_INFINITY;
         let mut t_max = f32::INFINITY;
@@ -661,11 +641,13 @@ impl OptimizedDiorama {
         t_max > 0.0
     }
     
-    pub fn ray_intersect_shadow_fast(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32) -> bool {
+    pub fn ray_intersect_shadow_fast(&self, ray_origin: &Vec3, ray_direction: &Vec3, max_distance: f32, 
+                                     textures: &TextureRegistry, time: f32) -> bool {
         for (i, cube) in self.cubes.iter().enumerate() {
             if i % 2 == 0 {
                 if let Some(distance) = cube.ray_intersect(ray_origin, ray_direction) {
-                    if distance > 0.001 && distance < max_distance {
+                    if distance > 0.001 && distance < max_distance 
+                        && Self::blocks_light(cube, ray_origin, ray_direction, distance, textures, time) {
                         return true;
                     }
                 }
@@ -673,6 +655,27 @@ impl OptimizedDiorama {
         }
         false
     }
+    
+    // Un cubo recortado solo proyecta sombra si el rayo cruza un texel opaco al entrar o al salir
+    fn blocks_light(cube: &Cube, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32, 
+                    textures: &TextureRegistry, time: f32) -> bool {
+        match cube.material.alpha_mode() {
+            AlphaMode::Opaque => true,
+            AlphaMode::Blend => false,
+            AlphaMode::Cutout(cutoff) => {
+                let entry_point = ray_origin + ray_direction * distance;
+                let inside = entry_point + ray_direction * 0.001;
+                let exit_point = match cube.ray_intersect(&inside, ray_direction) {
+                    Some(t) => inside + ray_direction * t,
+                    None => entry_point,
+                };
+                [entry_point, exit_point].iter().any(|point| {
+                    let (u, v) = cube.get_uv_coordinates(point);
+                    textures.alpha_at(&cube.material, u, v, time) >= cutoff
+                })
+            }
+        }
+    }
 }
 
 fn reflect(dir: &Vec3, normal: &Vec3) -> Vec3 { *dir - *normal * 2.0 * dot(dir, normal) }
@@ -702,27 +705,238 @@ fn fresnel(incident: &Vec3, normal: &Vec3, ior: f32) -> f32 {
     }
 }
 
-fn sample_sky(skybox: &Option<Skybox>, dir: &Vec3) -> Color {
-    if let Some(sb) = skybox {
-        let closer_dir = Vec3::new(dir.x * 0.3, dir.y * 0.7, dir.z * 0.3);
-        sb.sample(&closer_dir)
-    } else {
-        if dir.y > 0.1 {
-            let t = ((dir.y - 0.1) / 0.9).clamp(0.0, 1.0);
-            Color::new((100.0 + t * 80.0) as u8, (180.0 + t * 50.0) as u8, 255)
-        } else {
-            Color::new(120, 160, 200)
+fn radiance_to_color(radiance: &Vec3) -> Color {
+    Color::from_float(radiance.x, radiance.y, radiance.z)
+}
+
+fn sample_sky(scene: &Scene, dir: &Vec3) -> Color {
+    if let Some(env) = &scene.environment {
+        return radiance_to_color(&env.radiance(dir));
+    }
+    if let Some(sky) = &scene.sky {
+        return radiance_to_color(&sky.radiance(dir));
+    }
+    scene.skybox.sample(dir)
+}
+
+// Cielo de fondo con la capa de nubes compuesta encima, en espacio de color de pantalla
+fn sample_sky_with_clouds(scene: &Scene, origin: &Vec3, dir: &Vec3, time: f32) -> Color {
+    let background = sample_sky(scene, dir);
+    let Some(clouds) = &scene.clouds else {
+        return background;
+    };
+    let (sun_direction, sun_color) = scene.sun();
+    let lighting = CloudLighting {
+        sun_direction,
+        sun_color,
+        ambient: sample_sky(scene, &Vec3::new(0.0, 1.0, 0.0)).to_vec3() / 255.0,
+    };
+    let result = clouds.composite(origin, dir, background.to_vec3() / 255.0, &lighting, time);
+    Color::from_vec3(result * 255.0).clamp()
+}
+
+// Niebla entre el origen del rayo y lo que alcanzó; sin impacto se integra hasta `max_distance`
+fn apply_media(scene: &Scene, origin: &Vec3, dir: &Vec3, distance: f32, color: Color, ctx: &mut RayContext) -> Color {
+    let Some(fog) = &scene.fog else {
+        return color;
+    };
+    let length = distance.min(fog.max_distance);
+    let transmittance = fog.transmittance(origin, dir, length);
+    let fog_color = fog.color * 255.0;
+    if !fog.light_shafts {
+        return Color::from_vec3(color.to_vec3() * transmittance + fog_color * (1.0 - transmittance)).clamp();
+    }
+
+    // Dispersión simple: se marcha el rayo y cada muestra ve la luz principal solo si no está en sombra,
+    // así la luz que entra por las aberturas de la cueva forma haces visibles
+    let steps = fog.steps.max(1);
+    let step = length / steps as f32;
+    let jitter = ctx.rng.next_f32();
+    let mut scattered = fog_color * FOG_AMBIENT_SCATTER * (1.0 - transmittance);
+    for i in 0..steps {
+        let t = (i as f32 + jitter) * step;
+        let point = origin + dir * t;
+        let sigma = fog.density_at(&point);
+        let view_transmittance = fog.transmittance(origin, dir, t);
+        for (j, light) in scene.lights.iter().enumerate() {
+            let (light_dir, light_distance) = light.direction_from(&point);
+            if j == 0 && scene.diorama.ray_intersect_shadow_fast(&point, &light_dir, light_distance, &scene.textures, ctx.time) {
+                continue;
+            }
+            let attenuation = if light.direction.is_some() {
+                1.0
+            } else {
+                1.0 / (1.0 + 0.015 * light_distance + 0.0008 * light_distance * light_distance)
+            };
+            let phase = henyey_greenstein(dot(dir, &light_dir), fog.anisotropy) * 4.0 * PI;
+            let light_color = nalgebra_glm::matrix_comp_mult(&light.color.to_vec3(), &fog.color);
+            scattered += light_color * (light.intensity * attenuation * phase * sigma * view_transmittance * step);
+        }
+    }
+    Color::from_vec3(color.to_vec3() * transmittance + scattered).clamp()
+}
+
+// Irradiancia difusa del entorno (ya dividida por PI), muestreando direcciones según su brillo
+fn environment_diffuse(env: &EnvironmentMap, scene: &Scene, point: &Vec3, normal: &Vec3, 
+                       ctx: &mut RayContext) -> Vec3 {
+    let samples = scene.ibl_samples.max(1);
+    let mut sum = Vec3::new(0.0, 0.0, 0.0);
+    for _ in 0..samples {
+        let (dir, pdf) = env.sample_direction(ctx.rng.next_f32(), ctx.rng.next_f32());
+        let cos_theta = dot(normal, &dir);
+        if cos_theta <= 0.0 || pdf <= 0.0 {
+            continue;
+        }
+        if scene.diorama.ray_intersect_shadow_fast(point, &dir, f32::INFINITY, &scene.textures, ctx.time) {
+            continue;
         }
+        sum += env.radiance(&dir) * (cos_theta / pdf);
     }
+    sum / (samples as f32 * PI)
 }
 
-fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama: &OptimizedDiorama, floor: &Plane, 
-                                lights: &[Light], grass_texture: &Texture, dirt_texture: &Texture, stone_texture: &Texture, 
-                                water_texture: &Texture, lava_texture: &Texture, obsidian_texture: &Texture, 
-                                skybox: &Option<Skybox>, stats: &mut RenderStats, depth: u32) -> Color {
+// Reflejo brillante del entorno: direcciones del lóbulo Phong alrededor del reflejo especular
+fn environment_glossy(env: &EnvironmentMap, scene: &Scene, point: &Vec3, normal: &Vec3, view_dir: &Vec3, 
+                      exponent: f32, ctx: &mut RayContext) -> Vec3 {
+    let samples = scene.ibl_samples.max(1);
+    let mirror = reflect(view_dir, normal);
+    let mut sum = Vec3::new(0.0, 0.0, 0.0);
+    for _ in 0..samples {
+        let dir = sample_cosine_power(&mirror, exponent, ctx.rng.next_f32(), ctx.rng.next_f32());
+        if dot(normal, &dir) <= 0.0 {
+            continue;
+        }
+        if scene.diorama.ray_intersect_shadow_fast(point, &dir, f32::INFINITY, &scene.textures, ctx.time) {
+            continue;
+        }
+        let radiance = env.radiance(&dir);
+        let peak = radiance.max();
+        sum += if peak > IBL_SAMPLE_CLAMP { radiance * (IBL_SAMPLE_CLAMP / peak) } else { radiance };
+    }
+    sum / samples as f32
+}
+
+fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, ctx: &mut RayContext, 
+                                stats: &mut RenderStats, depth: u32) -> Color {
+    if depth > 0 {
+        // Se atraviesa el primer medio que el rayo alcance antes de chocar con una superficie;
+        // la prueba contra la caja del medio es barata, así que la escena solo se recorre si la cruza
+        let entered = scene.media.iter()
+            .filter_map(|medium| medium.ray_interval(ray_origin, ray_direction).map(|(t0, _)| (medium, t0)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, t0)| *t0 < first_hit_distance(scene, ray_origin, ray_direction));
+        if let Some((medium, t_enter)) = entered {
+            let entry = ray_origin + ray_direction * t_enter;
+            let color = trace_through_medium(medium, &entry, ray_direction, scene, ctx, stats, depth);
+            return apply_media(scene, ray_origin, ray_direction, t_enter, color, ctx);
+        }
+    }
+    trace_surfaces(ray_origin, ray_direction, scene, ctx, stats, depth)
+}
+
+// Distancia a la superficie más cercana, sin sombrear
+fn first_hit_distance(scene: &Scene, origin: &Vec3, direction: &Vec3) -> f32 {
+    let mut closest = f32::INFINITY;
+    if let Some((_, distance, object_type)) = scene.diorama.ray_intersect_fast(origin, direction)
+        && distance > 0.001 && object_type == 1 {
+        closest = distance;
+    }
+    if let Some(distance) = scene.floor.ray_intersect(origin, direction) {
+        closest = closest.min(distance);
+    }
+    closest
+}
+
+// Humo y calor: el rayo se curva al entrar, se traza lo que hay detrás y se compone el medio encima
+fn trace_through_medium(medium: &HeterogeneousMedium, entry: &Vec3, direction: &Vec3, scene: &Scene, 
+                        ctx: &mut RayContext, stats: &mut RenderStats, depth: u32) -> Color {
+    let time = ctx.time;
+    let bent = normalize(&(direction + medium.haze_offset(entry, time)));
+    let inside = entry + bent * 0.001;
+    let behind = trace_surfaces(&inside, &bent, scene, ctx, stats, depth);
+
+    let exit = medium.ray_interval(&inside, &bent).map_or(0.0, |(_, t1)| t1);
+    let length = exit.min(first_hit_distance(scene, &inside, &bent));
+    let steps = medium.steps.max(1);
+    let step = length / steps as f32;
+    let jitter = ctx.rng.next_f32();
+    let smoke = medium.smoke_color * (255.0 * (1.0 - medium.absorption));
+
+    let mut transmittance = 1.0;
+    let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+    for i in 0..steps {
+        let point = inside + bent * ((i as f32 + jitter) * step);
+        let sigma = medium.density_at(&point, time);
+        if sigma <= 0.0 {
+            continue;
+        }
+        let absorbed = 1.0 - (-sigma * step).exp();
+        // El humo se ilumina desde abajo por la lava además de emitir su propio resplandor
+        let glow = medium.emission_at(&point) * 255.0;
+        radiance += (smoke + glow) * (absorbed * transmittance);
+        transmittance *= 1.0 - absorbed;
+    }
+    Color::from_vec3(behind.to_vec3() * transmittance + radiance).clamp()
+}
+
+// Superficie más cercana que alcanzó un rayo
+#[derive(Clone, Copy)]
+struct SurfaceHit<'a> {
+    material: Option<Material>,
+    point: Vec3,
+    normal: Vec3,
+    object: u32,
+    cube: Option<&'a Cube>,
+    index: Option<usize>,
+    distance: f32,
+}
+
+fn trace_surfaces(ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, ctx: &mut RayContext, 
+                  stats: &mut RenderStats, depth: u32) -> Color {
     if depth == 0 {
-        return sample_sky(skybox, ray_direction);
+        return sample_sky(scene, ray_direction);
+    }
+
+    // Los texeles recortados dejan pasar el rayo sin gastar profundidad: se sigue desde el otro lado,
+    // con un tope de cruces para que una pila de hojas o rejas no lo alargue sin fin
+    let mut origin = *ray_origin;
+    let mut travelled = 0.0;
+    for _ in 0..MAX_CUTOUT_CROSSINGS {
+        let Some(hit) = closest_hit(&origin, ray_direction, scene, stats) else {
+            stats.misses += 1;
+            let background = sample_sky_with_clouds(scene, &origin, ray_direction, ctx.time);
+            return apply_media(scene, ray_origin, ray_direction, f32::INFINITY, background, ctx);
+        };
+        travelled += hit.distance;
+        let Some(surface_color) = shade_surface(hit, ray_direction, scene, ctx, stats, depth) else {
+            origin = hit.point + ray_direction * 0.001;
+            travelled += 0.001;
+            continue;
+        };
+
+        // El bloque seleccionado en el editor se aclara y se le marca el contorno, solo para los rayos primarios
+        let surface_color = match (scene.selection, hit.index, hit.cube) {
+            (Some((selected, _)), Some(index), Some(cube)) if selected == index && depth == MAX_DEPTH => {
+                let (u, v) = cube.get_uv_coordinates(&hit.point);
+                if u.min(v).min(1.0 - u).min(1.0 - v) < SELECTION_EDGE {
+                    Color::new(255, 255, 255)
+                } else {
+                    Color::from_vec3(surface_color.to_vec3() * 0.75 + Vec3::new(255.0, 255.0, 255.0) * 0.25)
+                }
+            }
+            _ => surface_color,
+        };
+
+        return apply_media(scene, ray_origin, ray_direction, travelled, surface_color, ctx);
     }
+    let background = sample_sky_with_clouds(scene, &origin, ray_direction, ctx.time);
+    apply_media(scene, ray_origin, ray_direction, f32::INFINITY, background, ctx)
+}
+
+// Superficie más cercana al frente del rayo, sin sombrear
+fn closest_hit<'a>(ray_origin: &Vec3, ray_direction: &Vec3, scene: &'a Scene, stats: &mut RenderStats) -> Option<SurfaceHit<'a>> {
+    let diorama = &scene.diorama;
+    let floor = &scene.floor;
 
     let mut closest_distance = f32::INFINITY;
     let mut hit_material: Option<Material> = None;
@@ -730,6 +944,7 @@ fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama
     let mut hit_normal = Vec3::new(0.0, 0.0, 0.0);
     let mut hit_object = 0;
     let mut hit_cube: Option<&Cube> = None;
+    let mut hit_index = None;
 
     stats.rays_cast += 1;
 
@@ -741,8 +956,8 @@ fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama
             hit_point = ray_origin + ray_direction * distance;
             hit_normal = cube.get_normal(&hit_point);
             hit_cube = Some(cube);
+            hit_index = Some(object_index);
             hit_object = 1;
-            stats.hits += 1;
         }
     }
 
@@ -753,32 +968,80 @@ fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama
             hit_normal = floor.get_normal(&hit_point);
             closest_distance = distance;
             hit_object = 5;
-            stats.hits += 1;
         }
     }
 
     if hit_object == 0 {
-        stats.misses += 1;
-        return sample_sky(skybox, ray_direction);
+        return None;
     }
+    // Se cuenta una vez, ya resuelto el impacto más cercano, aunque el suelo tape un cubo encontrado antes
+    stats.hits += 1;
+
+    Some(SurfaceHit {
+        material: hit_material,
+        point: hit_point,
+        normal: hit_normal,
+        object: hit_object,
+        cube: hit_cube,
+        index: hit_index,
+        distance: closest_distance,
+    })
+}
+
+// Color de la superficie alcanzada, sin contar la niebla entre la cámara y el impacto
+fn shade_surface(hit: SurfaceHit, ray_direction: &Vec3, scene: &Scene, ctx: &mut RayContext, 
+                 stats: &mut RenderStats, depth: u32) -> Option<Color> {
+    let diorama = &scene.diorama;
+    let floor = &scene.floor;
+    let textures = &scene.textures;
+    let time = ctx.time;
+    let SurfaceHit { material: hit_material, point: hit_point, normal: mut hit_normal, object: hit_object, 
+                     cube: hit_cube, distance: closest_distance, .. } = hit;
 
     if let Some(material) = hit_material {
-        let base_color = if hit_object == 1 && material.has_texture && hit_cube.is_some() {
+        // Huella del píxel en el mundo según la distancia, inclinada por el ángulo de incidencia
+        let cos_incidence = dot(&hit_normal, ray_direction).abs().max(0.05);
+        let spread = if ctx.orthographic { ctx.pixel_spread } else { closest_distance * ctx.pixel_spread };
+        let world_footprint = spread / cos_incidence;
+
+        let surface = if hit_object == 1 && hit_cube.is_some() {
             let cube = hit_cube.unwrap();
             let (u, v) = cube.get_uv_coordinates(&hit_point);
-            match material.material_type {
-                MaterialType::Grass => grass_texture.sample(u, v),
-                MaterialType::Dirt => dirt_texture.sample(u, v),
-                MaterialType::Stone => stone_texture.sample(u, v),
-                MaterialType::Water => water_texture.sample(u, v),
-                MaterialType::Lava => lava_texture.sample(u, v),
-                MaterialType::Obsidian => obsidian_texture.sample(u, v),
-                _ => material.diffuse,
-            }
+            let (tangent, bitangent) = cube.get_tangent_frame(&hit_point);
+            let face_size = cube.max.x - cube.min.x;
+            Some((u, v, tangent, bitangent, world_footprint / face_size))
+        } else if hit_object == 5 {
+            let (u, v) = floor.get_uv_coordinates(&hit_point);
+            let (tangent, bitangent) = floor.get_tangent_frame();
+            Some((u, v, tangent, bitangent, world_footprint))
         } else {
-            material.diffuse
+            None
+        };
+
+        let alpha_mode = material.alpha_mode();
+        let (base_color, alpha) = match surface {
+            Some((u, v, _, _, footprint)) => textures.sample(&material, u, v, footprint, time),
+            None => (material.diffuse, 1.0),
         };
 
+        // Los texeles recortados dejan pasar el rayo hacia lo que haya detrás
+        if let AlphaMode::Cutout(cutoff) = alpha_mode {
+            if alpha < cutoff {
+                return None;
+            }
+            // Las hojas y rejas se ven por ambas caras
+            if dot(&hit_normal, ray_direction) > 0.0 {
+                hit_normal = -hit_normal;
+            }
+        }
+
+        // La normal geométrica se conserva para desplazar los rayos secundarios fuera de la superficie
+        let geometric_normal = hit_normal;
+        if let Some((u, v, tangent, bitangent, footprint)) = surface {
+            let frame = SurfaceFrame { normal: geometric_normal, tangent, bitangent, u, v, footprint };
+            hit_normal = textures.perturb_normal(&material, &frame);
+        }
+
         let ambient_strength = match material.material_type {
             MaterialType::Grass => 0.5,
             MaterialType::Stone => 0.25,
@@ -793,27 +1056,46 @@ fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama
         let mut total_g = base_color.g as f32 * ambient_strength;
         let mut total_b = base_color.b as f32 * ambient_strength;
 
+        // Con un HDRI cargado, la luz ambiente fija se reemplaza por la iluminación del entorno
+        if let Some(env) = &scene.environment {
+            let ibl_origin = hit_point + geometric_normal * 0.001;
+            let irradiance = environment_diffuse(env, scene, &ibl_origin, &hit_normal, ctx);
+            total_r = base_color.r as f32 * irradiance.x;
+            total_g = base_color.g as f32 * irradiance.y;
+            total_b = base_color.b as f32 * irradiance.z;
+
+            if !material.is_reflective() && material.specular > 0.0 {
+                let glossy = environment_glossy(env, scene, &ibl_origin, &hit_normal, ray_direction, material.specular, ctx);
+                total_r += glossy.x * 255.0 * material.albedo[1];
+                total_g += glossy.y * 255.0 * material.albedo[1];
+                total_b += glossy.z * 255.0 * material.albedo[1];
+            }
+        }
+
         if material.is_emissive() {
             let ec = material.emission_color();
-            let ei = material.emission_intensity();
+            let ei = material.emission_intensity_at(time);
             total_r += ec.r as f32 * ei * 2.0;
             total_g += ec.g as f32 * ei * 2.0;
             total_b += ec.b as f32 * ei * 2.0;
         }
 
-        for (i, light) in lights.iter().enumerate() {
-            let light_dir = normalize(&(light.position - hit_point));
-            let light_distance = nalgebra_glm::distance(&light.position, &hit_point);
+        for (i, light) in scene.lights.iter().enumerate() {
+            let (light_dir, light_distance) = light.direction_from(&hit_point);
 
             let mut in_shadow = false;
             if i == 0 && material.material_type != MaterialType::Water {
-                let shadow_origin = hit_point + hit_normal * 0.001;
-                in_shadow = diorama.ray_intersect_shadow_fast(&shadow_origin, &light_dir, light_distance);
+                let shadow_origin = hit_point + geometric_normal * 0.001;
+                in_shadow = diorama.ray_intersect_shadow_fast(&shadow_origin, &light_dir, light_distance, textures, time);
             }
 
             if !in_shadow {
                 let diff = nalgebra_glm::dot(&hit_normal, &light_dir).max(0.0);
-                let attenuation = 1.0 / (1.0 + 0.015 * light_distance + 0.0008 * light_distance * light_distance);
+                let attenuation = if light.direction.is_some() {
+                    1.0
+                } else {
+                    1.0 / (1.0 + 0.015 * light_distance + 0.0008 * light_distance * light_distance)
+                };
 
                 let surface_multiplier = match material.material_type {
                     MaterialType::Grass => 1.4,
@@ -839,22 +1121,28 @@ fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama
             total_b.min(255.0) as u8,
         );
 
+        // Vidrio de color: lo que se ve detrás queda teñido por el texel en proporción a su transparencia
+        if alpha_mode == AlphaMode::Blend && alpha < 1.0 {
+            let through_origin = hit_point + ray_direction * 0.001;
+            let behind = cast_ray_optimized_recursive(&through_origin, ray_direction, scene, ctx, stats, depth - 1);
+            let tint = base_color.to_vec3() / 255.0;
+            let transmitted = nalgebra_glm::matrix_comp_mult(&behind.to_vec3(), &tint);
+            let mixed = final_color.to_vec3() * alpha + transmitted * (1.0 - alpha);
+            final_color = Color::from_vec3(mixed);
+        }
+
         let mut reflect_color = Color::black();
         if material.is_reflective() {
             let refl_dir = reflect(ray_direction, &hit_normal);
-            let refl_origin = hit_point + hit_normal * 0.001;
-            reflect_color = cast_ray_optimized_recursive(&refl_origin, &refl_dir, diorama, floor, lights,
-                                                        grass_texture, dirt_texture, stone_texture,
-                                                        water_texture, lava_texture, obsidian_texture, skybox, stats, depth - 1);
+            let refl_origin = hit_point + geometric_normal * 0.001;
+            reflect_color = cast_ray_optimized_recursive(&refl_origin, &refl_dir, scene, ctx, stats, depth - 1);
         }
 
         let mut refract_color = Color::black();
         if material.is_transparent() {
             if let Some(refr_dir) = refract(ray_direction, &hit_normal, material.refractive_index) {
-                let refr_origin = hit_point - hit_normal * 0.001;
-                refract_color = cast_ray_optimized_recursive(&refr_origin, &refr_dir, diorama, floor, lights,
-                                                            grass_texture, dirt_texture, stone_texture,
-                                                            water_texture, lava_texture, obsidian_texture, skybox, stats, depth - 1);
+                let refr_origin = hit_point - geometric_normal * 0.001;
+                refract_color = cast_ray_optimized_recursive(&refr_origin, &refr_dir, scene, ctx, stats, depth - 1);
             }
         }
 
@@ -866,16 +1154,16 @@ fn cast_ray_optimized_recursive(ray_origin: &Vec3, ray_direction: &Vec3, diorama
                 let refracted_part = refract_color.to_vec3() * (1.0 - kr) * t;
                 let base_part = final_color.to_vec3() * (1.0 - t);
                 let mixed = base_part + reflected_part + refracted_part;
-                return Color::from_vec3(mixed).clamp();
+                return Some(Color::from_vec3(mixed).clamp());
             } else {
                 let mixed = final_color.to_vec3() * (1.0 - kr) + reflect_color.to_vec3() * kr;
-                return Color::from_vec3(mixed).clamp();
+                return Some(Color::from_vec3(mixed).clamp());
             }
         }
 
-        final_color.clamp()
+        Some(final_color.clamp())
     } else {
-        sample_sky(skybox, ray_direction)
+        Some(sample_sky(scene, ray_direction))
     }
 }
 
@@ -901,105 +1189,540 @@ impl ColorVec3 for Color {
     }
 }
 
-fn main() {
-    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+fn default_camera(preset: ViewPreset) -> OrbitCamera {
+    let mut camera = OrbitCamera::new(Vec3::new(0.0, 2.0, 0.0), 10.0);
+    match preset.orbit_angles() {
+        Some((yaw, pitch)) => camera.set_angles(yaw, pitch),
+        None => camera.orbit(0.8, 0.4),
+    }
+    camera
+}
 
-    let grass_texture = match Texture::load_from_file("grass.png") {
-        Ok(tex) => tex,
-        Err(_) => Texture::create_grass_texture()
-    };
-    let dirt_texture = match Texture::load_from_file("dirt.png") {
-        Ok(tex) => tex,
-        Err(_) => Texture::create_dirt_texture()
-    };
-    let stone_texture = match Texture::load_from_file("stone.png") {
-        Ok(tex) => tex,
-        Err(_) => Texture::create_stone_texture()
-    };
-    let water_texture = match Texture::load_from_file("water.png") {
-        Ok(tex) => tex,
-        Err(_) => Texture::create_water_texture()
+fn main() {
+    let options = match CliOptions::parse() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
     };
-    let lava_texture = match Texture::load_from_file("lava.png") {
-        Ok(tex) => tex,
-        Err(_) => Texture::create_lava_texture()
+    if options.show_help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let mut scene = Scene::load(&options);
+    let mut preset = options.view;
+    let mut camera = default_camera(preset);
+    // Con --camera-position se parte de una cámara libre en ese punto mirando al centro del diorama
+    let placed_camera = options.camera_position.map(|position| FlyCamera::looking_at(position, camera.target));
+
+    let camera_path = match (&options.camera_path, options.turntable) {
+        (Some(path), _) => match CameraPath::load(path, options.fov) {
+            Ok(camera_path) => {
+                println!("Camera path loaded from {} ({} keyframes)", path, camera_path.keyframes.len());
+                Some(camera_path)
+            }
+            Err(e) => {
+                eprintln!("Failed to load camera path {}", e);
+                std::process::exit(1);
+            }
+        },
+        (None, Some(seconds)) => Some(CameraPath::turntable(&camera, seconds, options.fov)),
+        (None, None) => None,
     };
 
-    let obsidian_texture = match Texture::load_from_file("obsidian.png") {
-        Ok(tex) => tex,
-        Err(_) => Texture::create_obsidian_texture()
+    let input = match &options.bindings {
+        Some(path) => InputMap::load(path).unwrap_or_else(|e| {
+            eprintln!("Failed to load key bindings {}", e);
+            std::process::exit(1);
+        }),
+        None => InputMap::default(),
     };
 
-    let skybox = match Skybox::load_from_files() {
-        Ok(sb) => Some(sb),
+    if let Some(path) = &options.replay {
+        let frames = match session::load(path) {
+            Ok(frames) => frames,
+            Err(e) => {
+                eprintln!("Failed to load session {}", e);
+                std::process::exit(1);
+            }
+        };
+        println!("Replaying {} frames from {}", frames.len(), path);
+        replay_session(&mut scene, &frames, &options, &input);
+        return;
+    }
+
+    if let Some(output_dir) = &options.output_dir {
+        let offline_camera: &dyn Camera = match &placed_camera {
+            Some(fly) => fly,
+            None => &camera,
+        };
+        render_sequence(&scene, offline_camera, camera_path.as_ref(), &options, output_dir);
+        return;
+    }
+
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+    let mut projection = options.projection();
+
+    let mut window = Window::new("Minecraft Diorama - Water & Lava Expanded", options.width, options.height, WindowOptions::default()).unwrap();
+    window.set_target_fps(30);
+
+    let mut stats = RenderStats::new();
+    let start = Instant::now();
+    let mut stereo = options.stereo.map(|layout| options.stereo_settings(layout));
+    let mut mode = if camera_path.is_some() {
+        CameraMode::Path
+    } else if placed_camera.is_some() {
+        CameraMode::Fly
+    } else {
+        CameraMode::Orbit
+    };
+    let mut fly_camera = placed_camera.unwrap_or_else(|| FlyCamera::from_camera(&camera));
+    let mut last_frame = Instant::now();
+    let mut last_mouse: Option<(f32, f32)> = None;
+    // Un clic sin arrastrar elige el bloque bajo el cursor; arrastrar sigue moviendo la cámara
+    let mut press_position: Option<(f32, f32)> = None;
+    let palette = Material::palette();
+    let mut block = 0;
+    let mut hud = Hud::new(options.hud);
+    let mut screenshots = Screenshots::new(options.screenshot_settings());
+    let mut recorder = options.record.as_ref().map(|path| match SessionRecorder::create(path) {
+        Ok(recorder) => recorder,
         Err(e) => {
-            println!("Error loading skybox: {}", e);
-            None
+            eprintln!("Failed to create session {}: {}", path, e);
+            std::process::exit(1);
         }
+    });
+
+    while window.is_open() && !input.is_down(&window, Action::Quit) {
+        let frame_seconds = last_frame.elapsed().as_secs_f32();
+        let dt = frame_seconds.min(0.1);
+        last_frame = Instant::now();
+        hud.update(frame_seconds);
+        let fast = input.is_down(&window, Action::Fast);
+
+        if input.is_pressed(&window, Action::CycleCamera, KeyRepeat::No) {
+            mode = match mode {
+                CameraMode::Orbit => {
+                    fly_camera = FlyCamera::from_camera(&camera);
+                    CameraMode::Fly
+                }
+                CameraMode::Fly if camera_path.is_some() => CameraMode::Path,
+                CameraMode::Fly | CameraMode::Path => CameraMode::Orbit,
+            };
+            println!("Camera mode: {}", mode.name());
+        }
+
+        // Desplazamiento del ratón desde el cuadro anterior y giro de la rueda
+        let mouse = window.get_mouse_pos(MouseMode::Pass);
+        let (mouse_dx, mouse_dy) = match (mouse, last_mouse) {
+            (Some((x, y)), Some((last_x, last_y))) => ((x - last_x) * options.mouse_sensitivity, (y - last_y) * options.mouse_sensitivity),
+            _ => (0.0, 0.0),
+        };
+        last_mouse = mouse;
+        let scroll = window.get_scroll_wheel().map_or(0.0, |(_, scroll)| scroll);
+        let mut clicked = None;
+        if window.get_mouse_down(MouseButton::Left) {
+            press_position = press_position.or(mouse);
+        } else if let Some((start_x, start_y)) = press_position.take() {
+            clicked = mouse.filter(|(x, y)| (x - start_x).abs() + (y - start_y).abs() < 4.0);
+        }
+
+        match mode {
+            CameraMode::Orbit => {
+                let orbit_speed = if fast { 0.1 } else { 0.05 };
+                let zoom_speed = if fast { 1.2 } else { 0.6 };
+                camera.orbit(input.axis(&window, Action::OrbitRight, Action::OrbitLeft) * orbit_speed,
+                             input.axis(&window, Action::OrbitUp, Action::OrbitDown) * orbit_speed);
+                if window.get_mouse_down(MouseButton::Left) {
+                    camera.orbit(mouse_dx * 0.005, mouse_dy * 0.005);
+                }
+                // Con el botón derecho o central el punto bajo el cursor acompaña al ratón
+                if window.get_mouse_down(MouseButton::Right) || window.get_mouse_down(MouseButton::Middle) {
+                    let world_per_pixel = match projection.kind {
+                        ProjectionKind::Orthographic => projection.pixel_spread(framebuffer.height),
+                        _ => projection.pixel_spread(framebuffer.height) * camera.distance,
+                    };
+                    camera.pan(-mouse_dx * world_per_pixel, mouse_dy * world_per_pixel);
+                }
+                // En ortográfica acercarse es reducir el área visible, no mover la cámara
+                let mut zoom = input.axis(&window, Action::ZoomOut, Action::ZoomIn) * zoom_speed;
+                zoom -= scroll * 0.5 * options.scroll_sensitivity;
+                if zoom != 0.0 {
+                    match projection.kind {
+                        ProjectionKind::Orthographic => projection.set_ortho_height(projection.ortho_height + zoom),
+                        _ => camera.zoom(zoom),
+                    }
+                }
+            }
+            CameraMode::Fly => {
+                let axis = |positive: Action, negative: Action| input.axis(&window, positive, negative);
+                let boost = if fast { 3.0 } else { 1.0 };
+                fly_camera.translate(axis(Action::MoveForward, Action::MoveBack) * boost, 
+                                     axis(Action::MoveRight, Action::MoveLeft) * boost, 
+                                     axis(Action::MoveUp, Action::MoveDown) * boost, dt);
+                let look_speed = 1.5 * dt;
+                fly_camera.look(axis(Action::LookRight, Action::LookLeft) * look_speed, 
+                                axis(Action::LookUp, Action::LookDown) * look_speed);
+
+                // Mirar con el ratón mientras se arrastra con el botón izquierdo
+                if window.get_mouse_down(MouseButton::Left) {
+                    fly_camera.look(mouse_dx * 0.005, -mouse_dy * 0.005);
+                }
+
+                if input.is_pressed(&window, Action::SpeedUp, KeyRepeat::Yes) { fly_camera.adjust_speed(1.25); }
+                if input.is_pressed(&window, Action::SpeedDown, KeyRepeat::Yes) { fly_camera.adjust_speed(0.8); }
+                if scroll != 0.0 {
+                    fly_camera.adjust_speed(if scroll > 0.0 { 1.25 } else { 0.8 });
+                }
+            }
+            CameraMode::Path => {}
+        }
+        if input.is_pressed(&window, Action::FovDown, KeyRepeat::Yes) || input.is_pressed(&window, Action::FovUp, KeyRepeat::Yes) {
+            let delta = if input.is_down(&window, Action::FovDown) { -5.0 } else { 5.0 };
+            projection.set_fov(projection.fov_deg + delta);
+            println!("Field of view: {:.0}°", projection.fov_deg);
+        }
+        if input.is_pressed(&window, Action::CycleProjection, KeyRepeat::No) {
+            preset = preset.next();
+            projection.set_kind(preset.kind());
+            if let Some((yaw, pitch)) = preset.orbit_angles() {
+                camera.set_angles(yaw, pitch);
+            }
+            println!("Projection: {}", preset.name());
+        }
+        if input.is_pressed(&window, Action::CycleStereo, KeyRepeat::No) {
+            stereo = match stereo.map(|settings| settings.layout) {
+                None => Some(options.stereo_settings(StereoLayout::SideBySide)),
+                Some(StereoLayout::SideBySide) => Some(options.stereo_settings(StereoLayout::OverUnder)),
+                Some(StereoLayout::OverUnder) => None,
+            };
+            println!("Stereo: {}", stereo.map_or("off", |settings| settings.layout.name()));
+        }
+        if input.is_pressed(&window, Action::CycleFilter, KeyRepeat::No) {
+            scene.set_texture_filter(scene.textures.filter.next());
+            println!("Texture filter: {}", scene.textures.filter.name());
+        }
+        if input.is_pressed(&window, Action::ToggleHud, KeyRepeat::No) {
+            hud.visible = !hud.visible;
+        }
+        if input.is_down(&window, Action::Reset) {
+            camera = default_camera(preset);
+            fly_camera = FlyCamera::from_camera(&camera);
+        }
+
+        let time = options.start_time + start.elapsed().as_secs_f32();
+
+        stats.reset();
+        // El recorrido usa su propio campo de visión sin pisar el que se ajusta con las teclas
+        let mut view_projection = projection;
+        let path_camera = match (&camera_path, mode) {
+            (Some(camera_path), CameraMode::Path) => {
+                view_projection.set_fov(camera_path.sample(time).fov);
+                Some(PathCamera::new(camera_path, time))
+            }
+            _ => None,
+        };
+        let active: &dyn Camera = match (mode, &path_camera) {
+            (CameraMode::Path, Some(path_camera)) => path_camera,
+            (CameraMode::Fly, _) => &fly_camera,
+            _ => &camera,
+        };
+        // Editor de bloques: el rayo del cursor elige bloque y cara; en estéreo la imagen está dividida y no se usa
+        if let (Some((x, y)), None) = (clicked, stereo) {
+            // Mismo punto del píxel que la muestra sin jitter del render
+            let (ndc_x, ndc_y) = pixel_to_ndc(x.floor(), y.floor(), &framebuffer);
+            let (origin, direction) = active.generate_ray(&view_projection, ndc_x, ndc_y, (0.0, 0.0), time);
+            scene.selection = scene.diorama.pick(&origin, &direction);
+            if let Some((index, _)) = scene.selection {
+                println!("Selected {} block", scene.diorama.cubes[index].material.material_type.name());
+            }
+        }
+        if input.is_pressed(&window, Action::NextBlock, KeyRepeat::No) {
+            block = (block + 1) % palette.len();
+            println!("Block: {}", palette[block].material_type.name());
+        }
+        // Los cambios se guardan también en la sesión grabada para que la reproducción los repita
+        let mut edits = Vec::new();
+        if input.is_pressed(&window, Action::BreakBlock, KeyRepeat::No)
+            && let Some((index, _)) = scene.selection.take() {
+            let name = scene.diorama.cubes[index].material.material_type.name();
+            let edit = BlockEdit::Break(index);
+            scene.apply_edit(&edit);
+            edits.push(edit);
+            println!("Removed {} block", name);
+        }
+        if input.is_pressed(&window, Action::PlaceBlock, KeyRepeat::No)
+            && let Some((index, normal)) = scene.selection {
+            let edit = BlockEdit::Place(index, normal, palette[block]);
+            if scene.apply_edit(&edit) {
+                // El bloque nuevo queda seleccionado para poder seguir apilando sobre la misma cara
+                scene.selection = Some((scene.diorama.cubes.len() - 1, normal));
+                edits.push(edit);
+                println!("Placed {} block", palette[block].material_type.name());
+            } else {
+                println!("That space is already taken");
+            }
+        }
+
+        if options.autofocus {
+            autofocus(&scene, active, &mut view_projection, time);
+            // Si el centro apunta al cielo se conserva el último enfoque
+            projection.focus_distance = view_projection.focus_distance;
+        }
+        // Igual que el HUD, el resaltado del editor no sale en las capturas ni en su versión de alta calidad
+        let capturing = input.is_pressed(&window, Action::Screenshot, KeyRepeat::No);
+        let selection = scene.selection;
+        if capturing {
+            scene.selection = None;
+        }
+        render_view(&mut framebuffer, &scene, active, &view_projection, stereo.as_ref(), time, &mut stats);
+
+        if capturing {
+            let shot_camera = match &path_camera {
+                Some(path_camera) => ScreenshotCamera::Path(path_camera.path.clone(), path_camera.time),
+                None => ScreenshotCamera::fixed(active),
+            };
+            screenshots.capture(&framebuffer, &scene, shot_camera, &view_projection, stereo, time);
+            scene.selection = selection;
+        }
+
+        if let Some(recorder) = recorder.as_mut() {
+            let keys = window.get_keys().iter().map(|key| format!("{:?}", key)).collect();
+            let frame = SessionFrame::capture(time, active, &view_projection, stereo, scene.textures.filter, keys, edits);
+            if let Err(e) = recorder.record(&frame) {
+                eprintln!("Failed to record session frame: {}", e);
+            }
+        }
+
+        // El HUD se dibuja al final para que no aparezca en las capturas
+        if hud.visible {
+            let camera_line = match mode {
+                CameraMode::Orbit => format!("CAMERA ORBIT  YAW {:.0}  PITCH {:.0}  DISTANCE {:.1}", 
+                                             camera.yaw.to_degrees(), camera.pitch.to_degrees(), camera.distance),
+                CameraMode::Fly => format!("CAMERA FLY  YAW {:.0}  PITCH {:.0}  SPEED {:.1}", 
+                                           fly_camera.yaw.to_degrees(), fly_camera.pitch.to_degrees(), fly_camera.speed),
+                CameraMode::Path => format!("CAMERA PATH  T {:.1} S", time),
+            };
+            let eye = active.eye();
+            let lens = match view_projection.kind {
+                ProjectionKind::Orthographic => format!("HEIGHT {:.1}", view_projection.ortho_height),
+                _ => format!("FOV {:.0}", view_projection.fov_deg),
+            };
+            let lines = [
+                camera_line,
+                format!("EYE {:.1}, {:.1}, {:.1}", eye.x, eye.y, eye.z),
+                format!("{}  {}  SPP {}  FILTER {}", preset.name(), lens, scene.pixel_samples, scene.textures.filter.name()),
+                format!("STEREO {}  APERTURE {:.2}  FOCUS {:.1}", stereo.map_or("off", |settings| settings.layout.name()), 
+                        view_projection.aperture, view_projection.focus_distance),
+                format!("BLOCK {}  SELECTED {}", palette[block].material_type.name(), 
+                        scene.selection.map_or("none", |(index, _)| scene.diorama.cubes[index].material.material_type.name())),
+            ];
+            hud.draw(&mut framebuffer, &stats, &lines);
+        }
+
+        window.update_with_buffer(&framebuffer.buffer, options.width, options.height).unwrap();
+    }
+
+    if let (Some(recorder), Some(path)) = (recorder.as_mut(), &options.record) {
+        match recorder.finish() {
+            Ok(()) => println!("Recorded {} frames to {}", recorder.frames, path),
+            Err(e) => eprintln!("Failed to write session {}: {}", path, e),
+        }
+    }
+    if screenshots.is_rendering() {
+        println!("Waiting for the high quality screenshot to finish...");
+    }
+    screenshots.wait();
+}
+
+// Vuelve a dibujar una sesión grabada cuadro por cuadro: en la ventana, o sin ella (--headless o --output)
+// midiendo el tiempo de cada cuadro, lo que sirve como benchmark repetible
+fn replay_session(scene: &mut Scene, frames: &[SessionFrame], options: &CliOptions, input: &InputMap) {
+    let headless = options.headless || options.output_dir.is_some();
+    if let Some(output_dir) = &options.output_dir
+        && let Err(e) = std::fs::create_dir_all(output_dir) {
+        eprintln!("Failed to create {}: {}", output_dir, e);
+        std::process::exit(1);
+    }
+
+    let mut window = if headless {
+        None
+    } else {
+        let mut window = Window::new("Minecraft Diorama - Replay", options.width, options.height, WindowOptions::default()).unwrap();
+        window.set_target_fps(30);
+        Some(window)
     };
 
-    let mut camera = OrbitCamera::new(Vec3::new(0.0, 2.0, 0.0), 10.0);
-    camera.orbit(0.8, 0.4);
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+    let mut stats = RenderStats::new();
+    let mut totals = RenderStats::new();
+    let mut projection = options.projection();
+    let start = Instant::now();
+    let mut rendered = 0;
 
-    let diorama = OptimizedDiorama::new(Vec3::new(0.0, 0.0, 0.0), 0.8);
-    let floor = Plane::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Material::stone_wall());
+    for (index, frame) in frames.iter().enumerate() {
+        if let Some(window) = &window
+            && (!window.is_open() || input.is_down(window, Action::Quit)) {
+            break;
+        }
+        scene.set_texture_filter(frame.filter);
+        for edit in &frame.edits {
+            scene.apply_edit(edit);
+        }
+        frame.apply_to(&mut projection);
+        let stereo = frame.stereo_settings();
 
-    let lights = vec![
-        Light::new(Vec3::new(-4.0, 8.0, -2.0), Color::new(255, 220, 180), 1.3),
-        Light::new(Vec3::new(6.0, 6.0, 3.0), Color::new(180, 200, 255), 0.9),
-    ];
+        stats.reset();
+        render_view(&mut framebuffer, scene, &frame.camera(), &projection, stereo.as_ref(), frame.time, &mut stats);
+        totals.accumulate(&stats);
+        rendered += 1;
 
-    let mut window = Window::new("Minecraft Diorama - Water & Lava Expanded", WIDTH, HEIGHT, WindowOptions::default()).unwrap();
-    window.set_target_fps(30);
+        if let Some(output_dir) = &options.output_dir {
+            save_frame(&framebuffer, &format!("{}/frame_{:04}.png", output_dir, index), frame.time);
+        }
+        if let Some(window) = window.as_mut() {
+            window.update_with_buffer(&framebuffer.buffer, options.width, options.height).unwrap();
+        }
+    }
 
+    let elapsed = start.elapsed().as_secs_f32();
+    println!("Replayed {} frames in {:.2}s ({:.1} ms/frame, {:.1} fps)", rendered, elapsed, 
+             elapsed * 1000.0 / rendered.max(1) as f32, rendered as f32 / elapsed.max(1e-6));
+    totals.print_summary();
+}
+
+// Render offline: un PNG numerado por cuadro, avanzando el tiempo global según --fps
+fn render_sequence(scene: &Scene, camera: &dyn Camera, camera_path: Option<&CameraPath>, options: &CliOptions, 
+                   output_dir: &str) {
+    if let Err(e) = std::fs::create_dir_all(output_dir) {
+        eprintln!("Failed to create {}: {}", output_dir, e);
+        std::process::exit(1);
+    }
+
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
     let mut stats = RenderStats::new();
+    let mut projection = options.projection();
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        let orbit_speed = if window.is_key_down(Key::LeftShift) { 0.1 } else { 0.05 };
-        let zoom_speed = if window.is_key_down(Key::LeftShift) { 1.2 } else { 0.6 };
-        if window.is_key_down(Key::Left) { camera.orbit(-orbit_speed, 0.0); }
-        if window.is_key_down(Key::Right) { camera.orbit(orbit_speed, 0.0); }
-        if window.is_key_down(Key::Up) { camera.orbit(0.0, orbit_speed); }
-        if window.is_key_down(Key::Down) { camera.orbit(0.0, -orbit_speed); }
-        if window.is_key_down(Key::W) { camera.zoom(-zoom_speed); }
-        if window.is_key_down(Key::S) { camera.zoom(zoom_speed); }
-        if window.is_key_down(Key::Space) {
-            camera = OrbitCamera::new(Vec3::new(0.0, 2.0, 0.0), 10.0);
-            camera.orbit(0.8, 0.4);
+    // Caras de cubemap: cuadradas, de 90° y listas para cargarse con --skybox DIR/frame_0000_{}.png
+    let face_projection = Projection::new(90.0, SensorFit::Vertical, 1.0);
+    let stereo = options.stereo.map(|layout| options.stereo_settings(layout));
+    let mut face_buffer = Framebuffer::new(options.height, options.height);
+
+    // Sin --frames se exporta el recorrido completo: una vuelta del turntable o hasta el último keyframe
+    let frames = options.frames.unwrap_or_else(|| match camera_path {
+        Some(path) if path.period.is_some() => (path.duration() * options.fps).round().max(1.0) as u32,
+        Some(path) => {
+            let end = path.keyframes[0].time + path.duration();
+            ((end - options.start_time) * options.fps).floor().max(0.0) as u32 + 1
+        }
+        None => 1,
+    });
+
+    for frame in 0..frames {
+        let time = options.frame_time(frame);
+        let path_camera = camera_path.map(|path| PathCamera::new(path, time));
+        let camera: &dyn Camera = match &path_camera {
+            Some(path_camera) => path_camera,
+            None => camera,
+        };
+        if let Some(path) = camera_path {
+            projection.set_fov(path.sample(time).fov);
+        }
+        if options.autofocus {
+            autofocus(scene, camera, &mut projection, time);
+        }
+
+        if options.cube_faces {
+            for (name, face_camera) in cube_face_cameras(camera.eye()) {
+                stats.reset();
+                render_optimized_recursive(&mut face_buffer, scene, &face_camera, &face_projection, time, &mut stats);
+                save_frame(&face_buffer, &format!("{}/frame_{:04}_{}.png", output_dir, frame, name), time);
+            }
+            continue;
         }
 
         stats.reset();
-        render_optimized_recursive(&mut framebuffer, &diorama, &floor, &lights, &camera,
-                                  &grass_texture, &dirt_texture, &stone_texture, &water_texture, &lava_texture,
-                                  &obsidian_texture, &skybox, &mut stats);
+        render_view(&mut framebuffer, scene, camera, &projection, stereo.as_ref(), time, &mut stats);
+        save_frame(&framebuffer, &format!("{}/frame_{:04}.png", output_dir, frame), time);
+    }
+}
+
+fn save_frame(framebuffer: &Framebuffer, path: &str, time: f32) {
+    if let Err(e) = framebuffer.save_png(path) {
+        eprintln!("Failed to save {}: {}", path, e);
+        std::process::exit(1);
+    }
+    println!("Saved {} (t = {:.3}s)", path, time);
+}
 
-        window.update_with_buffer(&framebuffer.buffer, WIDTH, HEIGHT).unwrap();
+// Una sola imagen, o los dos ojos del par estéreo cada uno en su mitad del cuadro (izquierdo a la izquierda o arriba)
+fn render_view(framebuffer: &mut Framebuffer, scene: &Scene, camera: &dyn Camera, projection: &Projection, 
+               stereo: Option<&StereoSettings>, time: f32, stats: &mut RenderStats) {
+    let Some(stereo) = stereo else {
+        render_optimized_recursive(framebuffer, scene, camera, projection, time, stats);
+        return;
+    };
+    let (eye_width, eye_height, second_corner) = match stereo.layout {
+        StereoLayout::SideBySide => (framebuffer.width / 2, framebuffer.height, (framebuffer.width / 2, 0)),
+        StereoLayout::OverUnder => (framebuffer.width, framebuffer.height / 2, (0, framebuffer.height / 2)),
+    };
+    let mut eye_projection = *projection;
+    eye_projection.set_aspect(eye_width as f32 / eye_height as f32);
+    let mut eye_buffer = Framebuffer::new(eye_width, eye_height);
+    for (side, (x0, y0)) in [(-0.5, (0, 0)), (0.5, second_corner)] {
+        let eye = StereoEye { camera, offset: side * stereo.eye_separation, convergence: stereo.convergence };
+        render_optimized_recursive(&mut eye_buffer, scene, &eye, &eye_projection, time, stats);
+        framebuffer.blit(&eye_buffer, x0, y0);
+    }
+}
+
+// Enfoca lo que ve el centro de la imagen; si el rayo no toca nada se conserva el enfoque actual
+fn autofocus(scene: &Scene, camera: &dyn Camera, projection: &mut Projection, time: f32) {
+    let (origin, direction) = camera.generate_ray(projection, 0.0, 0.0, (0.0, 0.0), time);
+    let distance = first_hit_distance(scene, &origin, &direction);
+    if distance.is_finite() {
+        let (_, _, forward) = camera.basis();
+        projection.focus_distance = distance * dot(&direction, &forward);
     }
 }
 
-fn render_optimized_recursive(framebuffer: &mut Framebuffer, diorama: &OptimizedDiorama, floor: &Plane, 
-                              lights: &[Light], camera: &OrbitCamera, grass_texture: &Texture, 
-                              dirt_texture: &Texture, stone_texture: &Texture, water_texture: &Texture, 
-                              lava_texture: &Texture, obsidian_texture: &Texture, skybox: &Option<Skybox>, 
-                              stats: &mut RenderStats) {
+// Coordenadas de píxel a NDC; la esquina superior izquierda del píxel cae en su índice entero
+fn pixel_to_ndc(x: f32, y: f32, framebuffer: &Framebuffer) -> (f32, f32) {
+    let ndc_x = (2.0 * x) / framebuffer.width as f32 - 1.0;
+    let ndc_y = -(2.0 * y) / framebuffer.height as f32 + 1.0;
+    (ndc_x, ndc_y)
+}
+
+fn render_optimized_recursive(framebuffer: &mut Framebuffer, scene: &Scene, camera: &dyn Camera, 
+                              projection: &Projection, time: f32, stats: &mut RenderStats) {
     
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
+    let pixel_spread = projection.pixel_spread(framebuffer.height);
+    let orthographic = projection.kind == ProjectionKind::Orthographic;
+    let samples = scene.pixel_samples.max(1);
+    let frame_seed = time.to_bits();
     
     framebuffer.clear();
     
     let skip = 1;
     for y in (0..framebuffer.height).step_by(skip) {
         for x in (0..framebuffer.width).step_by(skip) {
-            let mut screen_x = (2.0 * x as f32) / width - 1.0;
-            let mut screen_y = -(2.0 * y as f32) / height + 1.0;
-            screen_x *= aspect_ratio;
-            
-            let ray_direction = camera.get_ray_direction(screen_x, screen_y);
-            let pixel_color = cast_ray_optimized_recursive(&camera.eye, &ray_direction, diorama, floor, 
-                                                         lights, grass_texture, dirt_texture, stone_texture, 
-                                                         water_texture, lava_texture, obsidian_texture, skybox, stats, MAX_DEPTH);
+            let mut ctx = RayContext { time, pixel_spread, orthographic, rng: Rng::for_pixel(x, y, frame_seed) };
+            let mut accumulated = Vec3::new(0.0, 0.0, 0.0);
+            for _ in 0..samples {
+                // Con varias muestras cada una cae en un punto distinto del píxel y de la lente
+                let (jitter_x, jitter_y) = if samples > 1 { (ctx.rng.next_f32(), ctx.rng.next_f32()) } else { (0.0, 0.0) };
+                let lens = if projection.aperture > 0.0 { sample_disk(ctx.rng.next_f32(), ctx.rng.next_f32()) } else { (0.0, 0.0) };
+                let (screen_x, screen_y) = pixel_to_ndc(x as f32 + jitter_x, y as f32 + jitter_y, framebuffer);
+                
+                // Cada muestra cae en un instante distinto mientras el obturador está abierto
+                ctx.time = if scene.shutter > 0.0 { time + ctx.rng.next_f32() * scene.shutter } else { time };
+                let (ray_origin, ray_direction) = camera.generate_ray(projection, screen_x, screen_y, lens, ctx.time);
+                let color = cast_ray_optimized_recursive(&ray_origin, &ray_direction, scene, &mut ctx, stats, MAX_DEPTH);
+                accumulated += color.to_vec3();
+            }
+            let pixel_color = Color::from_vec3(accumulated / samples as f32);
             
             framebuffer.set_current_color(pixel_color);
             for dy in 0..skip {
@@ -1011,4 +1734,4 @@ fn render_optimized_recursive(framebuffer: &mut Framebuffer, diorama: &Optimized
             }
         }
     }
-}
\ No newline at end of file
+}
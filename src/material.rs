@@ -21,6 +21,57 @@ pub enum MaterialType {
     Glass,
     Metal,
     Obsidian, // NUEVO: Obsidiana agregada
+    Leaves,
+    StainedGlass,
+    Grate,
+}
+
+// Cómo se interpreta el canal alfa de la textura del material
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    Cutout(f32),
+    Blend,
+}
+
+impl MaterialType {
+    pub const COUNT: usize = 12;
+    
+    pub const ALL: [MaterialType; MaterialType::COUNT] = [
+        MaterialType::Grass,
+        MaterialType::Dirt,
+        MaterialType::Stone,
+        MaterialType::Water,
+        MaterialType::Lava,
+        MaterialType::Wood,
+        MaterialType::Glass,
+        MaterialType::Metal,
+        MaterialType::Obsidian,
+        MaterialType::Leaves,
+        MaterialType::StainedGlass,
+        MaterialType::Grate,
+    ];
+    
+    pub fn id(&self) -> usize {
+        *self as usize
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaterialType::Grass => "grass",
+            MaterialType::Dirt => "dirt",
+            MaterialType::Stone => "stone",
+            MaterialType::Water => "water",
+            MaterialType::Lava => "lava",
+            MaterialType::Wood => "wood",
+            MaterialType::Glass => "glass",
+            MaterialType::Metal => "metal",
+            MaterialType::Obsidian => "obsidian",
+            MaterialType::Leaves => "leaves",
+            MaterialType::StainedGlass => "stained_glass",
+            MaterialType::Grate => "grate",
+        }
+    }
 }
 
 impl Material {
@@ -157,6 +208,57 @@ impl Material {
         }
     }
     
+    pub fn leaves_block() -> Self {
+        Material {
+            diffuse: Color::new(60, 140, 45),
+            specular: 4.0,
+            albedo: [0.9, 0.1],
+            refractive_index: 1.0,
+            has_texture: true,
+            material_type: MaterialType::Leaves,
+        }
+    }
+    
+    pub fn stained_glass() -> Self {
+        Material {
+            diffuse: Color::new(180, 60, 120),
+            specular: 30.0,
+            albedo: [0.8, 0.2],
+            refractive_index: 1.0,
+            has_texture: true,
+            material_type: MaterialType::StainedGlass,
+        }
+    }
+    
+    pub fn grate_block() -> Self {
+        Material {
+            diffuse: Color::new(70, 70, 75),
+            specular: 40.0,
+            albedo: [0.7, 0.3],
+            refractive_index: 1.0,
+            has_texture: true,
+            material_type: MaterialType::Grate,
+        }
+    }
+    
+    // Bloques que se pueden colocar desde el editor de la ventana, en el orden en que se recorren
+    pub fn palette() -> Vec<Material> {
+        vec![
+            Material::grass_top(),
+            Material::dirt_layer(),
+            Material::stone_layer(),
+            Material::wood_planks(),
+            Material::obsidian_block(),
+            Material::lava_surface(),
+            Material::water_surface(),
+            Material::clear_glass(),
+            Material::stained_glass(),
+            Material::metal_surface(),
+            Material::leaves_block(),
+            Material::grate_block(),
+        ]
+    }
+    
     pub fn is_emissive(&self) -> bool {
         matches!(self.material_type, MaterialType::Lava)
     }
@@ -169,6 +271,14 @@ impl Material {
         self.specular > 50.0 || matches!(self.material_type, MaterialType::Water | MaterialType::Metal | MaterialType::Obsidian)
     }
     
+    pub fn alpha_mode(&self) -> AlphaMode {
+        match self.material_type {
+            MaterialType::Leaves | MaterialType::Grate => AlphaMode::Cutout(0.5),
+            MaterialType::StainedGlass => AlphaMode::Blend,
+            _ => AlphaMode::Opaque,
+        }
+    }
+    
     pub fn emission_intensity(&self) -> f32 {
         match self.material_type {
             MaterialType::Lava => 0.4,
@@ -176,6 +286,14 @@ impl Material {
         }
     }
     
+    // La lava pulsa lentamente; el resto de materiales emite de forma constante
+    pub fn emission_intensity_at(&self, time: f32) -> f32 {
+        match self.material_type {
+            MaterialType::Lava => self.emission_intensity() * (1.0 + 0.2 * (time * 1.7).sin()),
+            _ => self.emission_intensity(),
+        }
+    }
+    
     pub fn emission_color(&self) -> Color {
         match self.material_type {
             MaterialType::Lava => Color::new(255, 150, 50),
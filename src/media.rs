@@ -0,0 +1,140 @@
+use crate::noise::{fbm, value_noise};
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+// Distribución espacial de la niebla
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FogShape {
+    Uniform,
+    // La densidad indicada corresponde a `base_height` y decae exponencialmente al subir
+    Height { base_height: f32, falloff: f32 },
+}
+
+// Medio participante que llena la escena: atenúa lo que se ve y dispersa la luz hacia la cámara
+#[derive(Debug, Clone)]
+pub struct Fog {
+    pub shape: FogShape,
+    pub density: f32,
+    pub color: Vec3,
+    pub max_distance: f32,
+    pub light_shafts: bool,
+    pub steps: u32,
+    pub anisotropy: f32,
+}
+
+impl Fog {
+    pub fn new(shape: FogShape, density: f32, color: Vec3) -> Self {
+        Fog { shape, density, color, max_distance: 60.0, light_shafts: false, steps: 12, anisotropy: 0.3 }
+    }
+    
+    pub fn density_at(&self, point: &Vec3) -> f32 {
+        match self.shape {
+            FogShape::Uniform => self.density,
+            FogShape::Height { base_height, falloff } => self.density * (-(point.y - base_height) * falloff).exp(),
+        }
+    }
+    
+    // Integral analítica de la densidad a lo largo del rayo
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        match self.shape {
+            FogShape::Uniform => self.density * distance,
+            FogShape::Height { falloff, .. } => {
+                let start = self.density_at(origin);
+                let rate = falloff * direction.y;
+                if rate.abs() < 1e-4 {
+                    start * distance
+                } else {
+                    start * (1.0 - (-rate * distance).exp()) / rate
+                }
+            }
+        }
+    }
+    
+    pub fn transmittance(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        (-self.optical_depth(origin, direction, distance)).exp()
+    }
+}
+
+// Función de fase de Henyey-Greenstein (g > 0 dispersa hacia delante)
+pub fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+}
+
+// Medio heterogéneo acotado por una caja: humo que sube con densidad de ruido, brilla cerca de su base
+// (la lava) y desvía ligeramente los rayos que lo cruzan, como el aire caliente
+#[derive(Debug, Clone)]
+pub struct HeterogeneousMedium {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub density: f32,
+    pub absorption: f32,
+    pub smoke_color: Vec3,
+    pub emission_color: Vec3,
+    pub emission_strength: f32,
+    pub noise_scale: f32,
+    pub rise_speed: f32,
+    pub haze_strength: f32,
+    pub steps: u32,
+}
+
+impl HeterogeneousMedium {
+    pub fn lava_smoke(min: Vec3, max: Vec3) -> Self {
+        HeterogeneousMedium {
+            min,
+            max,
+            density: 3.0,
+            absorption: 0.6,
+            smoke_color: Vec3::new(0.35, 0.3, 0.28),
+            emission_color: Vec3::new(1.0, 0.35, 0.08),
+            emission_strength: 2.5,
+            noise_scale: 1.6,
+            rise_speed: 0.6,
+            haze_strength: 0.03,
+            steps: 16,
+        }
+    }
+    
+    // Tramo del rayo dentro de la caja (método de las placas)
+    pub fn ray_interval(&self, origin: &Vec3, direction: &Vec3) -> Option<(f32, f32)> {
+        let mut t_near: f32 = 0.0;
+        let mut t_far = f32::INFINITY;
+        for axis in 0..3 {
+            let inv = 1.0 / direction[axis];
+            let t0 = (self.min[axis] - origin[axis]) * inv;
+            let t1 = (self.max[axis] - origin[axis]) * inv;
+            t_near = t_near.max(t0.min(t1));
+            t_far = t_far.min(t0.max(t1));
+        }
+        if t_near < t_far { Some((t_near, t_far)) } else { None }
+    }
+    
+    // Altura relativa dentro de la caja: 0 en la base, 1 en la tapa
+    fn height_fraction(&self, point: &Vec3) -> f32 {
+        ((point.y - self.min.y) / (self.max.y - self.min.y)).clamp(0.0, 1.0)
+    }
+    
+    pub fn density_at(&self, point: &Vec3, time: f32) -> f32 {
+        let h = self.height_fraction(point);
+        // Desvanecer hacia los bordes laterales para que no se note la caja
+        let size = self.max - self.min;
+        let edge_x = ((point.x - self.min.x).min(self.max.x - point.x) / (size.x * 0.25)).clamp(0.0, 1.0);
+        let edge_z = ((point.z - self.min.z).min(self.max.z - point.z) / (size.z * 0.25)).clamp(0.0, 1.0);
+        let rising = (point - Vec3::new(0.0, self.rise_speed * time, 0.0)) * self.noise_scale;
+        let noise = ((fbm(&rising, 4) - 0.35) / 0.65).max(0.0);
+        noise * (1.0 - h).powf(1.5) * edge_x * edge_z * self.density
+    }
+    
+    // Resplandor de la lava, que se apaga al alejarse de la base
+    pub fn emission_at(&self, point: &Vec3) -> Vec3 {
+        self.emission_color * (self.emission_strength * (-self.height_fraction(point) * 4.0).exp())
+    }
+    
+    // Desviación de la dirección del rayo por el aire caliente, más fuerte junto a la lava
+    pub fn haze_offset(&self, point: &Vec3, time: f32) -> Vec3 {
+        let p = (point - Vec3::new(0.0, self.rise_speed * 2.0 * time, 0.0)) * (self.noise_scale * 2.0);
+        let dx = value_noise(&p) - 0.5;
+        let dz = value_noise(&(p + Vec3::new(31.7, 0.0, 11.3))) - 0.5;
+        Vec3::new(dx, 0.0, dz) * (self.haze_strength * (1.0 - self.height_fraction(point)))
+    }
+}
@@ -0,0 +1,47 @@
+use nalgebra_glm::Vec3;
+
+fn hash(x: i32, y: i32, z: i32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(374_761_393)
+        ^ (y as u32).wrapping_mul(668_265_263)
+        ^ (z as u32).wrapping_mul(2_147_483_647);
+    h = (h ^ (h >> 13)).wrapping_mul(1_274_126_177);
+    h ^= h >> 16;
+    (h & 0x00FF_FFFF) as f32 / 0x00FF_FFFF as f32
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Ruido de valor 3D con interpolación suave, en [0, 1]
+pub fn value_noise(p: &Vec3) -> f32 {
+    let ix = p.x.floor();
+    let iy = p.y.floor();
+    let iz = p.z.floor();
+    let fx = smooth(p.x - ix);
+    let fy = smooth(p.y - iy);
+    let fz = smooth(p.z - iz);
+    let (x, y, z) = (ix as i32, iy as i32, iz as i32);
+    
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = lerp(hash(x, y, z), hash(x + 1, y, z), fx);
+    let x10 = lerp(hash(x, y + 1, z), hash(x + 1, y + 1, z), fx);
+    let x01 = lerp(hash(x, y, z + 1), hash(x + 1, y, z + 1), fx);
+    let x11 = lerp(hash(x, y + 1, z + 1), hash(x + 1, y + 1, z + 1), fx);
+    lerp(lerp(x00, x10, fy), lerp(x01, x11, fy), fz)
+}
+
+// Suma de octavas de ruido (movimiento browniano fraccional), normalizada a [0, 1]
+pub fn fbm(p: &Vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut total = 0.0;
+    let mut point = *p;
+    for _ in 0..octaves {
+        sum += value_noise(&point) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        point = point * 2.03 + Vec3::new(17.1, 3.7, 9.2);
+    }
+    sum / total
+}
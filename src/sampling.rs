@@ -0,0 +1,65 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+// Generador xorshift64*: determinista por píxel para que los renders sean reproducibles
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed.max(1) }
+    }
+    
+    pub fn for_pixel(x: usize, y: usize, frame_seed: u32) -> Self {
+        let mut seed = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (frame_seed as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+        seed ^= seed >> 33;
+        Rng::new(seed)
+    }
+    
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    
+    // Uniforme en [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+pub fn orthonormal_basis(n: &Vec3) -> (Vec3, Vec3) {
+    let helper = if n.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = nalgebra_glm::normalize(&nalgebra_glm::cross(&helper, n));
+    let bitangent = nalgebra_glm::cross(n, &tangent);
+    (tangent, bitangent)
+}
+
+// Dirección distribuida como cos^exponent alrededor de `axis` (exponente 1 = coseno, lóbulo Phong si es mayor)
+pub fn sample_cosine_power(axis: &Vec3, exponent: f32, u1: f32, u2: f32) -> Vec3 {
+    let cos_theta = u1.powf(1.0 / (exponent + 1.0));
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * u2;
+    let (tangent, bitangent) = orthonormal_basis(axis);
+    nalgebra_glm::normalize(&(tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta))
+}
+
+// Punto uniforme en el disco unidad (mapeo concéntrico de Shirley, sin amontonar muestras en el centro)
+pub fn sample_disk(u1: f32, u2: f32) -> (f32, f32) {
+    let a = 2.0 * u1 - 1.0;
+    let b = 2.0 * u2 - 1.0;
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (radius, theta) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (radius * theta.cos(), radius * theta.sin())
+}
@@ -0,0 +1,148 @@
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::camera::{Camera, FixedCamera, Projection, StereoSettings};
+use crate::camera_path::{CameraPath, PathCamera};
+use crate::framebuffer::Framebuffer;
+use crate::stats::RenderStats;
+use crate::Scene;
+
+// Dónde y con qué calidad se guardan las capturas de la ventana
+#[derive(Debug, Clone)]
+pub struct ScreenshotSettings {
+    pub directory: String,
+    // Factor de resolución y muestras por píxel del render de alta calidad; 1 y 0 lo desactivan
+    pub scale: usize,
+    pub samples: u32,
+}
+
+impl ScreenshotSettings {
+    fn wants_hires(&self) -> bool {
+        self.scale > 1 || self.samples > 0
+    }
+}
+
+// Fecha y hora UTC como `AAAAMMDD_HHMMSS_mmm`, para que las capturas se ordenen por nombre
+pub fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let time_of_day = seconds % 86_400;
+    format!("{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}", year, month, day,
+            time_of_day / 3600, time_of_day / 60 % 60, time_of_day % 60, now.subsec_millis())
+}
+
+// Días desde 1970-01-01 a fecha del calendario gregoriano (algoritmo de Howard Hinnant)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Guarda lo que muestra la ventana y devuelve la ruta base (sin extensión) de la captura
+fn save(framebuffer: &Framebuffer, settings: &ScreenshotSettings) -> Option<String> {
+    if let Err(e) = std::fs::create_dir_all(&settings.directory) {
+        eprintln!("Failed to create {}: {}", settings.directory, e);
+        return None;
+    }
+    let base = format!("{}/screenshot_{}", settings.directory, timestamp());
+    let path = format!("{}.png", base);
+    match framebuffer.save_png(&path) {
+        Ok(()) => {
+            println!("Saved screenshot {}", path);
+            Some(base)
+        }
+        Err(e) => {
+            eprintln!("Failed to save {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Cámara que se manda al hilo del render de alta calidad; un recorrido viaja entero para que, con el
+// obturador abierto, cada muestra se evalúe en su propio instante igual que en la ventana
+pub enum ScreenshotCamera {
+    Fixed(FixedCamera),
+    Path(CameraPath, f32),
+}
+
+impl ScreenshotCamera {
+    pub fn fixed(camera: &dyn Camera) -> Self {
+        let (right, up, forward) = camera.basis();
+        ScreenshotCamera::Fixed(FixedCamera { eye: camera.eye(), right, up, forward })
+    }
+}
+
+// Capturas de la ventana: como mucho hay un render de alta calidad en curso a la vez
+pub struct Screenshots {
+    settings: ScreenshotSettings,
+    pending: Option<JoinHandle<()>>,
+}
+
+impl Screenshots {
+    pub fn new(settings: ScreenshotSettings) -> Self {
+        Screenshots { settings, pending: None }
+    }
+    
+    pub fn is_rendering(&self) -> bool {
+        self.pending.as_ref().is_some_and(|handle| !handle.is_finished())
+    }
+    
+    // Guarda la vista actual y, si se pidió más calidad, la vuelve a renderizar en otro hilo con más resolución
+    // y muestras; la escena se copia (las imágenes se comparten) para que la ventana pueda seguir cambiándola
+    pub fn capture(&mut self, framebuffer: &Framebuffer, scene: &Scene, camera: ScreenshotCamera, projection: &Projection,
+                   stereo: Option<StereoSettings>, time: f32) {
+        let Some(base) = save(framebuffer, &self.settings) else {
+            return;
+        };
+        if !self.settings.wants_hires() {
+            return;
+        }
+        if self.is_rendering() {
+            println!("Still rendering the previous high quality screenshot, skipping it for this one");
+            return;
+        }
+        self.wait();
+        println!("Rendering high quality screenshot in the background...");
+        let size = (framebuffer.width, framebuffer.height);
+        let mut scene = scene.clone();
+        if self.settings.samples > 0 {
+            scene.pixel_samples = self.settings.samples;
+        }
+        let projection = *projection;
+        let scale = self.settings.scale.max(1);
+        self.pending = Some(std::thread::spawn(move || {
+            let mut framebuffer = Framebuffer::new(size.0 * scale, size.1 * scale);
+            let mut stats = RenderStats::new();
+            let path_camera;
+            let camera: &dyn Camera = match &camera {
+                ScreenshotCamera::Fixed(fixed) => fixed,
+                ScreenshotCamera::Path(path, time) => {
+                    path_camera = PathCamera::new(path, *time);
+                    &path_camera
+                }
+            };
+            crate::render_view(&mut framebuffer, &scene, camera, &projection, stereo.as_ref(), time, &mut stats);
+            let path = format!("{}_hires.png", base);
+            match framebuffer.save_png(&path) {
+                Ok(()) => println!("Saved screenshot {} ({}x{}, {} spp)", path, framebuffer.width, framebuffer.height, scene.pixel_samples),
+                Err(e) => eprintln!("Failed to save {}: {}", path, e),
+            }
+        }));
+    }
+    
+    // Espera a que termine el render en curso, avisando si el hilo falló
+    pub fn wait(&mut self) {
+        if let Some(handle) = self.pending.take()
+            && handle.join().is_err() {
+            eprintln!("High quality screenshot render panicked");
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::camera::{Camera, FixedCamera, Projection, ProjectionKind, StereoLayout, StereoSettings};
+use crate::material::Material;
+use crate::texture::TextureFilter;
+use nalgebra_glm::Vec3;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+const HEADER: &str = "proyecto2-session 2";
+
+// Cambio hecho con el editor de bloques; al reproducir se aplica en el mismo cuadro y orden
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockEdit {
+    Break(usize),
+    // Bloque vecino, normal de la cara donde se pega y material del bloque nuevo
+    Place(usize, Vec3, Material),
+}
+
+impl BlockEdit {
+    // `break:índice` o `place:índice:x,y,z:material`, sin espacios para caber en un campo de la línea
+    fn to_token(self) -> String {
+        match self {
+            BlockEdit::Break(index) => format!("break:{}", index),
+            BlockEdit::Place(index, normal, material) => {
+                format!("place:{}:{},{},{}:{}", index, normal.x, normal.y, normal.z, material.material_type.name())
+            }
+        }
+    }
+    
+    fn parse_token(token: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = token.split(':').collect();
+        let index = |text: &str| text.parse().map_err(|_| format!("invalid edit {}", token));
+        match parts.as_slice() {
+            ["break", i] => Ok(BlockEdit::Break(index(i)?)),
+            ["place", i, normal, name] => {
+                let n: Vec<f32> = normal.split(',').map(|c| c.parse()).collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid edit {}", token))?;
+                let [x, y, z] = n.as_slice() else {
+                    return Err(format!("invalid edit {}", token));
+                };
+                let material = Material::palette().into_iter().find(|m| m.material_type.name() == *name)
+                    .ok_or_else(|| format!("invalid edit {}", token))?;
+                Ok(BlockEdit::Place(index(i)?, Vec3::new(*x, *y, *z), material))
+            }
+            _ => Err(format!("invalid edit {}", token)),
+        }
+    }
+}
+
+// Estado completo de un cuadro de la ventana: con él se vuelve a dibujar exactamente lo mismo
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionFrame {
+    pub time: f32,
+    pub eye: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+    pub forward: Vec3,
+    pub projection: ProjectionKind,
+    pub fov: f32,
+    pub ortho_height: f32,
+    pub aperture: f32,
+    pub focus_distance: f32,
+    pub stereo: Option<StereoLayout>,
+    pub eye_separation: f32,
+    pub convergence: f32,
+    pub filter: TextureFilter,
+    // Teclas pulsadas en ese cuadro, solo como referencia de lo que hizo quien grabó
+    pub keys: Vec<String>,
+    pub edits: Vec<BlockEdit>,
+}
+
+impl SessionFrame {
+    pub fn capture(time: f32, camera: &dyn Camera, projection: &Projection, stereo: Option<StereoSettings>, 
+                   filter: TextureFilter, keys: Vec<String>, edits: Vec<BlockEdit>) -> Self {
+        let (right, up, forward) = camera.basis();
+        SessionFrame {
+            time,
+            eye: camera.eye(),
+            right,
+            up,
+            forward,
+            projection: projection.kind,
+            fov: projection.fov_deg,
+            ortho_height: projection.ortho_height,
+            aperture: projection.aperture,
+            focus_distance: projection.focus_distance,
+            stereo: stereo.map(|settings| settings.layout),
+            eye_separation: stereo.map_or(0.0, |settings| settings.eye_separation),
+            convergence: stereo.map_or(0.0, |settings| settings.convergence),
+            filter,
+            keys,
+            edits,
+        }
+    }
+    
+    pub fn camera(&self) -> FixedCamera {
+        FixedCamera { eye: self.eye, right: self.right, up: self.up, forward: self.forward }
+    }
+    
+    pub fn stereo_settings(&self) -> Option<StereoSettings> {
+        self.stereo.map(|layout| StereoSettings { layout, eye_separation: self.eye_separation, convergence: self.convergence })
+    }
+    
+    // Aplica la lente grabada sobre `projection`, que conserva la proporción de la imagen actual
+    pub fn apply_to(&self, projection: &mut Projection) {
+        projection.set_kind(self.projection);
+        projection.set_fov(self.fov);
+        projection.set_ortho_height(self.ortho_height);
+        projection.aperture = self.aperture;
+        projection.focus_distance = self.focus_distance;
+    }
+    
+    fn to_line(&self) -> String {
+        let vec = |v: &Vec3| format!("{},{},{}", v.x, v.y, v.z);
+        format!(
+            "t={} eye={} right={} up={} forward={} projection={} fov={} ortho={} aperture={} focus={} stereo={} eyes={} convergence={} filter={} keys={} edits={}",
+            self.time, vec(&self.eye), vec(&self.right), vec(&self.up), vec(&self.forward),
+            self.projection.name(), self.fov, self.ortho_height, self.aperture, self.focus_distance,
+            self.stereo.map_or("off", |layout| layout.name()), self.eye_separation, self.convergence,
+            self.filter.name(), self.keys.join(","),
+            self.edits.iter().map(|edit| edit.to_token()).collect::<Vec<_>>().join(";"),
+        )
+    }
+    
+    fn parse_line(line: &str) -> Result<Self, String> {
+        let field = |name: &str| -> Result<&str, String> {
+            line.split_whitespace()
+                .find_map(|token| token.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')))
+                .ok_or_else(|| format!("missing {}", name))
+        };
+        let number = |name: &str| -> Result<f32, String> {
+            field(name)?.parse().map_err(|_| format!("invalid {}", name))
+        };
+        let vector = |name: &str| -> Result<Vec3, String> {
+            let parts: Vec<f32> = field(name)?.split(',').map(|c| c.parse()).collect::<Result<_, _>>()
+                .map_err(|_| format!("invalid {}", name))?;
+            match parts.as_slice() {
+                [x, y, z] => Ok(Vec3::new(*x, *y, *z)),
+                _ => Err(format!("invalid {}", name)),
+            }
+        };
+        let stereo = match field("stereo")? {
+            "off" => None,
+            name => Some(StereoLayout::from_name(name).ok_or("invalid stereo")?),
+        };
+        Ok(SessionFrame {
+            time: number("t")?,
+            eye: vector("eye")?,
+            right: vector("right")?,
+            up: vector("up")?,
+            forward: vector("forward")?,
+            projection: ProjectionKind::from_name(field("projection")?).ok_or("invalid projection")?,
+            fov: number("fov")?,
+            ortho_height: number("ortho")?,
+            aperture: number("aperture")?,
+            focus_distance: number("focus")?,
+            stereo,
+            eye_separation: number("eyes")?,
+            convergence: number("convergence")?,
+            filter: TextureFilter::from_name(field("filter")?).ok_or("invalid filter")?,
+            keys: field("keys").unwrap_or("").split(',').filter(|k| !k.is_empty()).map(String::from).collect(),
+            edits: field("edits").unwrap_or("").split(';').filter(|e| !e.is_empty())
+                .map(BlockEdit::parse_token).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+// Graba una línea por cuadro mientras la ventana está abierta
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+    pub frames: usize,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADER)?;
+        Ok(SessionRecorder { writer, frames: 0 })
+    }
+    
+    pub fn record(&mut self, frame: &SessionFrame) -> std::io::Result<()> {
+        self.frames += 1;
+        writeln!(self.writer, "{}", frame.to_line())
+    }
+    
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub fn load(path: &str) -> Result<Vec<SessionFrame>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some(HEADER) {
+        return Err(format!("{}: not a session recording", path));
+    }
+    lines.enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| SessionFrame::parse_line(line).map_err(|e| format!("{}:{}: {}", path, number + 2, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn frame_survives_a_line_round_trip() {
+        let frame = SessionFrame {
+            time: 1.25,
+            eye: Vec3::new(8.0, 6.5, -3.1),
+            right: Vec3::new(0.6, 0.0, 0.8),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            forward: Vec3::new(-0.8, 0.0, 0.6),
+            projection: ProjectionKind::Orthographic,
+            fov: 70.0,
+            ortho_height: 12.5,
+            aperture: 0.15,
+            focus_distance: 9.75,
+            stereo: Some(StereoLayout::OverUnder),
+            eye_separation: 0.065,
+            convergence: 7.0,
+            filter: TextureFilter::Trilinear,
+            keys: vec!["W".to_string(), "LeftShift".to_string()],
+            edits: vec![BlockEdit::Break(12), BlockEdit::Place(40, Vec3::new(0.0, 1.0, 0.0), Material::stained_glass())],
+        };
+        assert_eq!(SessionFrame::parse_line(&frame.to_line()), Ok(frame));
+    }
+    
+    #[test]
+    fn frame_without_stereo_or_keys_round_trips() {
+        let frame = SessionFrame {
+            time: 0.0,
+            eye: Vec3::new(0.0, 2.0, 10.0),
+            right: Vec3::new(1.0, 0.0, 0.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            forward: Vec3::new(0.0, 0.0, -1.0),
+            projection: ProjectionKind::Perspective,
+            fov: 90.0,
+            ortho_height: 12.0,
+            aperture: 0.0,
+            focus_distance: 10.0,
+            stereo: None,
+            eye_separation: 0.0,
+            convergence: 0.0,
+            filter: TextureFilter::Nearest,
+            keys: Vec::new(),
+            edits: Vec::new(),
+        };
+        assert_eq!(SessionFrame::parse_line(&frame.to_line()), Ok(frame));
+    }
+}
@@ -0,0 +1,122 @@
+use nalgebra_glm::Vec3;
+
+// Convierte la luminancia del modelo (kcd/m²) a la radiancia relativa que se muestra sin recortar:
+// con el sol a media altura y turbidez 3 el cenit queda en torno a 0.3
+const LUMINANCE_SCALE: f32 = 0.05;
+
+// Cielo analítico de Preetham et al. (1999): luminancia y cromaticidad por dirección
+// a partir de la posición del sol y la turbidez de la atmósfera
+#[derive(Debug, Clone)]
+pub struct PreethamSky {
+    pub sun_direction: Vec3,
+    pub turbidity: f32,
+    pub exposure: f32,
+    pub sun_angular_radius: f32,
+    pub sun_intensity: f32,
+    perez_y: [f32; 5],
+    perez_x: [f32; 5],
+    perez_yy: [f32; 5],
+    zenith: Vec3,
+}
+
+fn perez(theta_cos: f32, gamma: f32, coeffs: &[f32; 5]) -> f32 {
+    let [a, b, c, d, e] = *coeffs;
+    (1.0 + a * (b / theta_cos).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+    if y <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Vec3::new(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
+
+impl PreethamSky {
+    pub fn new(sun_direction: Vec3, turbidity: f32) -> Self {
+        let sun_direction = nalgebra_glm::normalize(&sun_direction);
+        let t = turbidity.clamp(1.7, 10.0);
+        // El modelo no está definido con el sol bajo el horizonte; se mantiene justo encima
+        let theta_s = sun_direction.y.clamp(0.01, 1.0).acos();
+        
+        let chi = (4.0 / 9.0 - t / 120.0) * (std::f32::consts::PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (t2, th, th2, th3) = (t * t, theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+        
+        PreethamSky {
+            sun_direction,
+            turbidity: t,
+            exposure: 1.0,
+            // Unas cuatro veces el tamaño real para que el disco se vea a baja resolución
+            sun_angular_radius: 1.0_f32.to_radians(),
+            sun_intensity: 25.0,
+            perez_y: [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            perez_x: [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            perez_yy: [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+            zenith: Vec3::new(zenith_x, zenith_y, zenith_luminance),
+        }
+    }
+    
+    // Dirección desde elevación y azimut en grados; azimut 0 mira hacia -Z y 90 hacia +X
+    pub fn sun_direction_from_angles(elevation_deg: f32, azimuth_deg: f32) -> Vec3 {
+        let elevation = elevation_deg.to_radians();
+        let azimuth = azimuth_deg.to_radians();
+        Vec3::new(elevation.cos() * azimuth.sin(), elevation.sin(), -elevation.cos() * azimuth.cos())
+    }
+    
+    // Color del cielo sin el disco solar, en radiancia lineal relativa
+    pub fn sky_radiance(&self, direction: &Vec3) -> Vec3 {
+        let dir = nalgebra_glm::normalize(direction);
+        // Bajo el horizonte se refleja el cielo cercano al horizonte sobre un suelo oscuro
+        let below = dir.y < 0.0;
+        let theta_cos = dir.y.abs().max(0.02);
+        let up = Vec3::new(dir.x, theta_cos, dir.z);
+        let gamma = nalgebra_glm::dot(&nalgebra_glm::normalize(&up), &self.sun_direction).clamp(-1.0, 1.0).acos();
+        let theta_s = self.sun_direction.y.clamp(0.01, 1.0).acos();
+        
+        let ratio = |coeffs: &[f32; 5]| perez(theta_cos, gamma, coeffs) / perez(1.0, theta_s, coeffs);
+        let x = self.zenith.x * ratio(&self.perez_x);
+        let y = self.zenith.y * ratio(&self.perez_yy);
+        let luminance = self.zenith.z * ratio(&self.perez_y) * LUMINANCE_SCALE * self.exposure;
+        
+        let color = xyy_to_rgb(x, y, luminance);
+        if below { color * 0.3 } else { color }
+    }
+    
+    // Color de la luz solar directa tras atravesar la atmósfera (enrojece cerca del horizonte)
+    pub fn sun_color(&self) -> Vec3 {
+        let elevation = self.sun_direction.y.clamp(0.01, 1.0).asin().to_degrees();
+        let zenith_angle = 90.0 - elevation;
+        // Masa de aire de Kasten y Young
+        let air_mass = 1.0 / (zenith_angle.to_radians().cos() + 0.50572 * (96.07995 - zenith_angle).powf(-1.6364));
+        let optical_depth = Vec3::new(0.03, 0.06, 0.13) * (self.turbidity / 2.0);
+        Vec3::new(
+            (-air_mass * optical_depth.x).exp(),
+            (-air_mass * optical_depth.y).exp(),
+            (-air_mass * optical_depth.z).exp(),
+        )
+    }
+    
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let dir = nalgebra_glm::normalize(direction);
+        let mut color = self.sky_radiance(&dir);
+        let angle = nalgebra_glm::dot(&dir, &self.sun_direction).clamp(-1.0, 1.0).acos();
+        if angle < self.sun_angular_radius {
+            // Oscurecimiento hacia el borde del disco
+            let limb = 1.0 - 0.6 * (angle / self.sun_angular_radius).powi(2);
+            color += self.sun_color() * self.sun_intensity * limb;
+        }
+        color
+    }
+}
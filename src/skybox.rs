@@ -0,0 +1,309 @@
+use crate::color::Color;
+use crate::texture::Texture;
+use nalgebra_glm::Vec3;
+
+// Distribución de las seis caras en el archivo de origen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CubemapLayout {
+    // Seis archivos; la ruta contiene `{}`, que se sustituye por px, nx, py, ny, pz y nz
+    Faces,
+    // Cruz 4x3:      +Y
+    //           -X  +Z  +X  -Z
+    //                -Y
+    HorizontalCross,
+    // Cruz 3x4 con -Z girada 180° en la fila inferior
+    VerticalCross,
+    // Tiras de seis caras en orden +X, -X, +Y, -Y, +Z, -Z
+    HorizontalStrip,
+    VerticalStrip,
+}
+
+impl CubemapLayout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "faces" => Some(CubemapLayout::Faces),
+            "hcross" => Some(CubemapLayout::HorizontalCross),
+            "vcross" => Some(CubemapLayout::VerticalCross),
+            "hstrip" => Some(CubemapLayout::HorizontalStrip),
+            "vstrip" => Some(CubemapLayout::VerticalStrip),
+            _ => None,
+        }
+    }
+    
+    // Deduce la distribución de una imagen única a partir de su proporción
+    pub fn detect(width: u32, height: u32) -> Option<Self> {
+        if width * 3 == height * 4 {
+            Some(CubemapLayout::HorizontalCross)
+        } else if width * 4 == height * 3 {
+            Some(CubemapLayout::VerticalCross)
+        } else if width == height * 6 {
+            Some(CubemapLayout::HorizontalStrip)
+        } else if height == width * 6 {
+            Some(CubemapLayout::VerticalStrip)
+        } else {
+            None
+        }
+    }
+}
+
+// Ajustes explícitos del cielo; con los valores por defecto la búsqueda en el cubemap no se deforma
+#[derive(Debug, Clone, Copy)]
+pub struct SkyboxOptions {
+    pub rotation_deg: f32,
+    pub horizon_offset_deg: f32,
+    pub exposure: f32,
+}
+
+impl Default for SkyboxOptions {
+    fn default() -> Self {
+        SkyboxOptions {
+            rotation_deg: 0.0,
+            horizon_offset_deg: 0.0,
+            exposure: 1.0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Skybox {
+    pub px: Texture,
+    pub nx: Texture,
+    pub py: Texture,
+    pub ny: Texture,
+    pub pz: Texture,
+    pub nz: Texture,
+    pub options: SkyboxOptions,
+}
+
+impl Skybox {
+    pub fn create_procedural_sky() -> Self {
+        Skybox {
+            px: Self::create_sky_texture_right(),
+            nx: Self::create_sky_texture_left(), 
+            py: Self::create_sky_texture_top(),
+            ny: Self::create_sky_texture_bottom(),
+            pz: Self::create_sky_texture_front(),
+            nz: Self::create_sky_texture_back(),
+            options: SkyboxOptions::default(),
+        }
+    }
+
+    // `layout` en None intenta deducirlo: rutas con `{}` son caras sueltas y las imágenes únicas se detectan por proporción
+    pub fn load(path: &str, layout: Option<CubemapLayout>) -> Result<Self, Box<dyn std::error::Error>> {
+        let layout = match layout {
+            Some(layout) => layout,
+            None if path.contains("{}") => CubemapLayout::Faces,
+            None => {
+                let (width, height) = image::image_dimensions(path)?;
+                CubemapLayout::detect(width, height)
+                    .ok_or_else(|| format!("cannot detect cubemap layout of {}x{} image {}", width, height, path))?
+            }
+        };
+        
+        if layout == CubemapLayout::Faces {
+            let face = |name: &str| Texture::load_from_file(&path.replace("{}", name));
+            return Ok(Skybox {
+                px: face("px")?,
+                nx: face("nx")?,
+                py: face("py")?,
+                ny: face("ny")?,
+                pz: face("pz")?,
+                nz: face("nz")?,
+                options: SkyboxOptions::default(),
+            });
+        }
+        
+        let image = Texture::load_from_file(path)?;
+        Self::from_single_image(&image, layout)
+    }
+    
+    pub fn from_single_image(image: &Texture, layout: CubemapLayout) -> Result<Self, Box<dyn std::error::Error>> {
+        // Posición (columna, fila) de cada cara en unidades de cara: +X, -X, +Y, -Y, +Z, -Z
+        let (columns, rows, cells): (u32, u32, [(u32, u32); 6]) = match layout {
+            CubemapLayout::HorizontalCross => (4, 3, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)]),
+            CubemapLayout::VerticalCross => (3, 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)]),
+            CubemapLayout::HorizontalStrip => (6, 1, [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0)]),
+            CubemapLayout::VerticalStrip => (1, 6, [(0, 0), (0, 1), (0, 2), (0, 3), (0, 4), (0, 5)]),
+            CubemapLayout::Faces => return Err("a single image cannot use the faces layout".into()),
+        };
+        
+        let size = image.width / columns;
+        if size == 0 || image.width != size * columns || image.height != size * rows {
+            return Err(format!("{}x{} image does not match a {}x{} cubemap layout", 
+                               image.width, image.height, columns, rows).into());
+        }
+        
+        let face = |index: usize| {
+            let (column, row) = cells[index];
+            image.crop(column * size, row * size, size, size)
+        };
+        let mut nz = face(5);
+        if layout == CubemapLayout::VerticalCross {
+            nz = nz.rotated_180();
+        }
+        
+        Ok(Skybox {
+            px: face(0),
+            nx: face(1),
+            py: face(2),
+            ny: face(3),
+            pz: face(4),
+            nz,
+            options: SkyboxOptions::default(),
+        })
+    }
+    
+    fn create_sky_texture_top() -> Texture {
+        let size = 256;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let cloud_noise = ((x * 7 + y * 13) % 64) as f32 / 64.0;
+                let cloud_intensity = if cloud_noise > 0.7 { 0.8 } else { 0.2 };
+                
+                let base_r = (130.0 + cloud_intensity * 100.0) as u8;
+                let base_g = (180.0 + cloud_intensity * 50.0) as u8;
+                let base_b = 255;
+                
+                data.extend_from_slice(&[base_r, base_g, base_b, 255]);
+            }
+        }
+        Texture { width: size, height: size, data }
+    }
+    
+    fn create_sky_texture_bottom() -> Texture {
+        let size = 256;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for _x in 0..size {
+                let fade = y as f32 / size as f32;
+                let r = (80.0 + fade * 50.0) as u8;
+                let g = (120.0 + fade * 60.0) as u8;
+                let b = (200.0 + fade * 55.0) as u8;
+                
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: size, height: size, data }
+    }
+    
+    fn create_sky_texture_front() -> Texture {
+        let size = 256;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let fade = (size - y) as f32 / size as f32;
+                let sun_x = size / 4;
+                let sun_y = size / 3;
+                let dist = ((x as f32 - sun_x as f32).powi(2) + (y as f32 - sun_y as f32).powi(2)).sqrt();
+                let sun_effect = if dist < 30.0 { 0.8 } else { 0.0 };
+                
+                let r = (100.0 + fade * 80.0 + sun_effect * 100.0) as u8;
+                let g = (150.0 + fade * 80.0 + sun_effect * 80.0) as u8;
+                let b = (220.0 + fade * 35.0) as u8;
+                
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: size, height: size, data }
+    }
+    
+    fn create_sky_texture_back() -> Texture {
+        let size = 256;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for _x in 0..size {
+                let fade = (size - y) as f32 / size as f32;
+                let r = (90.0 + fade * 60.0) as u8;
+                let g = (140.0 + fade * 70.0) as u8;
+                let b = (210.0 + fade * 45.0) as u8;
+                
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: size, height: size, data }
+    }
+    
+    fn create_sky_texture_left() -> Texture {
+        let size = 256;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for _x in 0..size {
+                let fade = (size - y) as f32 / size as f32;
+                let r = (95.0 + fade * 65.0) as u8;
+                let g = (145.0 + fade * 75.0) as u8;
+                let b = (215.0 + fade * 40.0) as u8;
+                
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: size, height: size, data }
+    }
+    
+    fn create_sky_texture_right() -> Texture {
+        let size = 256;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for _x in 0..size {
+                let fade = (size - y) as f32 / size as f32;
+                let r = (105.0 + fade * 55.0) as u8;
+                let g = (155.0 + fade * 65.0) as u8;
+                let b = (225.0 + fade * 30.0) as u8;
+                
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: size, height: size, data }
+    }
+    
+    // Rotación alrededor del eje vertical y desplazamiento del horizonte: la elevación e pasa a
+    // e - offset * cos(e), de modo que el horizonte se mueve `offset` grados y los polos quedan fijos
+    fn adjust_direction(&self, dir: &Vec3) -> Vec3 {
+        let mut dir = *dir;
+        
+        if self.options.rotation_deg != 0.0 {
+            let (sin_r, cos_r) = self.options.rotation_deg.to_radians().sin_cos();
+            dir = Vec3::new(dir.x * cos_r - dir.z * sin_r, dir.y, dir.x * sin_r + dir.z * cos_r);
+        }
+        
+        if self.options.horizon_offset_deg != 0.0 {
+            let horizontal = (dir.x * dir.x + dir.z * dir.z).sqrt();
+            let elevation = dir.y.atan2(horizontal);
+            let adjusted = (elevation - self.options.horizon_offset_deg.to_radians() * elevation.cos())
+                .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2);
+            let scale = if horizontal > 1e-6 { adjusted.cos() / horizontal } else { 0.0 };
+            dir = Vec3::new(dir.x * scale, adjusted.sin(), dir.z * scale);
+        }
+        
+        dir
+    }
+    
+    // Búsqueda estándar de cubemap (convención OpenGL): el eje dominante elige la cara
+    pub fn sample(&self, direction: &Vec3) -> Color {
+        let dir = self.adjust_direction(&nalgebra_glm::normalize(direction));
+        
+        let abs_x = dir.x.abs();
+        let abs_y = dir.y.abs();
+        let abs_z = dir.z.abs();
+        
+        let (texture, sc, tc, ma) = if abs_x >= abs_y && abs_x >= abs_z {
+            if dir.x > 0.0 { (&self.px, -dir.z, -dir.y, abs_x) } else { (&self.nx, dir.z, -dir.y, abs_x) }
+        } else if abs_y >= abs_x && abs_y >= abs_z {
+            if dir.y > 0.0 { (&self.py, dir.x, dir.z, abs_y) } else { (&self.ny, dir.x, -dir.z, abs_y) }
+        } else {
+            if dir.z > 0.0 { (&self.pz, dir.x, -dir.y, abs_z) } else { (&self.nz, -dir.x, -dir.y, abs_z) }
+        };
+        
+        // Se sujeta al centro de los texeles del borde para que el filtrado no cruce a la cara opuesta
+        let u = ((sc / ma + 1.0) * 0.5).clamp(0.5 / texture.width as f32, 1.0 - 0.5 / texture.width as f32);
+        let v = ((tc / ma + 1.0) * 0.5).clamp(0.5 / texture.height as f32, 1.0 - 0.5 / texture.height as f32);
+        let color = texture.sample_bilinear(u, v);
+        
+        if self.options.exposure == 1.0 {
+            color
+        } else {
+            let e = self.options.exposure;
+            Color::from_float(color.r as f32 / 255.0 * e, color.g as f32 / 255.0 * e, color.b as f32 / 255.0 * e)
+        }
+    }
+}
@@ -15,6 +15,13 @@ impl RenderStats {
         *self = Self::default();
     }
     
+    pub fn accumulate(&mut self, other: &RenderStats) {
+        self.rays_cast += other.rays_cast;
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.objects_tested += other.objects_tested;
+    }
+    
     pub fn print_summary(&self) {
         println!("=== Render Stats ===");
         println!("Rays cast: {}", self.rays_cast);
@@ -0,0 +1,696 @@
+use crate::color::Color;
+use crate::material::{Material, MaterialType};
+use image::open;
+use nalgebra_glm::Vec3;
+use std::f32::consts::TAU;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    Trilinear,
+}
+
+impl TextureFilter {
+    pub fn next(self) -> Self {
+        match self {
+            TextureFilter::Nearest => TextureFilter::Bilinear,
+            TextureFilter::Bilinear => TextureFilter::Trilinear,
+            TextureFilter::Trilinear => TextureFilter::Nearest,
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(TextureFilter::Nearest),
+            "bilinear" => Some(TextureFilter::Bilinear),
+            "trilinear" => Some(TextureFilter::Trilinear),
+            _ => None,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "nearest",
+            TextureFilter::Bilinear => "bilinear",
+            TextureFilter::Trilinear => "trilinear",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Texture {
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let img = open(path)?;
+        let rgba_img = img.to_rgba8();
+        let (width, height) = rgba_img.dimensions();
+        let data = rgba_img.into_raw();
+        Ok(Texture { width, height, data })
+    }
+    
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        let u = (u.fract() + 1.0).fract().clamp(0.0, 1.0);
+        let v = (v.fract() + 1.0).fract().clamp(0.0, 1.0);
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        let index = ((y * self.width + x) * 4) as usize;
+        
+        if index + 3 < self.data.len() {
+            Color::new(self.data[index], self.data[index + 1], self.data[index + 2])
+        } else {
+            Color::new(255, 0, 255)
+        }
+    }
+    
+    pub fn sample_alpha(&self, u: f32, v: f32) -> f32 {
+        let u = (u.fract() + 1.0).fract().clamp(0.0, 1.0);
+        let v = (v.fract() + 1.0).fract().clamp(0.0, 1.0);
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.texel(x, y)[3] / 255.0
+    }
+    
+    fn texel(&self, x: u32, y: u32) -> [f32; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        [self.data[index] as f32, self.data[index + 1] as f32, self.data[index + 2] as f32, self.data[index + 3] as f32]
+    }
+    
+    pub fn sample_bilinear_rgba(&self, u: f32, v: f32) -> [f32; 4] {
+        // Centros de texel en (i + 0.5); las coordenadas se envuelven igual que en `sample`
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = x - x0;
+        let ty = y - y0;
+        
+        let wrap = |value: f32, size: u32| (value as i64).rem_euclid(size as i64) as u32;
+        let xa = wrap(x0, self.width);
+        let xb = wrap(x0 + 1.0, self.width);
+        let ya = wrap(y0, self.height);
+        let yb = wrap(y0 + 1.0, self.height);
+        
+        let c00 = self.texel(xa, ya);
+        let c10 = self.texel(xb, ya);
+        let c01 = self.texel(xa, yb);
+        let c11 = self.texel(xb, yb);
+        
+        let mut out = [0.0; 4];
+        for i in 0..4 {
+            let top = c00[i] + (c10[i] - c00[i]) * tx;
+            let bottom = c01[i] + (c11[i] - c01[i]) * tx;
+            out[i] = top + (bottom - top) * ty;
+        }
+        out
+    }
+    
+    pub fn sample_bilinear(&self, u: f32, v: f32) -> Color {
+        let [r, g, b, _] = self.sample_bilinear_rgba(u, v);
+        Color::new(r.round() as u8, g.round() as u8, b.round() as u8)
+    }
+    
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Texture {
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for row in y..y + height {
+            let start = ((row * self.width + x) * 4) as usize;
+            data.extend_from_slice(&self.data[start..start + (width * 4) as usize]);
+        }
+        Texture { width, height, data }
+    }
+    
+    pub fn rotated_180(&self) -> Texture {
+        let mut data = Vec::with_capacity(self.data.len());
+        for pixel in self.data.chunks(4).rev() {
+            data.extend_from_slice(pixel);
+        }
+        Texture { width: self.width, height: self.height, data }
+    }
+    
+    // Reduce a la mitad promediando bloques de 2x2 (las dimensiones impares repiten el borde)
+    pub fn downsample(&self) -> Texture {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let sx0 = (x * 2).min(self.width - 1);
+                let sx1 = (x * 2 + 1).min(self.width - 1);
+                let sy0 = (y * 2).min(self.height - 1);
+                let sy1 = (y * 2 + 1).min(self.height - 1);
+                let texels = [self.texel(sx0, sy0), self.texel(sx1, sy0), self.texel(sx0, sy1), self.texel(sx1, sy1)];
+                for i in 0..4 {
+                    let sum: f32 = texels.iter().map(|t| t[i]).sum();
+                    data.push((sum / 4.0).round() as u8);
+                }
+            }
+        }
+        Texture { width, height, data }
+    }
+    
+    pub fn create_grass_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let noise1 = ((x * 17 + y * 13) % 16) as f32 / 16.0;
+                let noise2 = ((x * 7 + y * 11) % 8) as f32 / 8.0;
+                let combined_noise = (noise1 + noise2 * 0.3).clamp(0.0, 1.0);
+                let base_green = 160 + (combined_noise * 60.0) as u8;
+                let r = (25.0 + combined_noise * 35.0) as u8;
+                let b = (25.0 + combined_noise * 30.0) as u8;
+                data.extend_from_slice(&[r, base_green, b, 255]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_stone_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let noise1 = ((x * 23 + y * 19) % 32) as f32 / 32.0;
+                let noise2 = ((x * 7 + y * 13) % 16) as f32 / 16.0;
+                let combined_noise = (noise1 + noise2 * 0.4).clamp(0.0, 1.0);
+                let base_gray = (70.0 + combined_noise * 40.0) as u8;
+                let variation = (combined_noise * 15.0) as u8;
+                data.extend_from_slice(&[ base_gray + variation, base_gray + (variation / 2), base_gray, 255]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_dirt_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let noise1 = ((x * 13 + y * 17) % 24) as f32 / 24.0;
+                let noise2 = ((x * 29 + y * 7) % 16) as f32 / 16.0;
+                let combined_noise = (noise1 + noise2 * 0.5).clamp(0.0, 1.0);
+                let brown_r = (140.0 + combined_noise * 50.0) as u8;
+                let brown_g = (85.0 + combined_noise * 35.0) as u8;
+                let brown_b = (35.0 + combined_noise * 25.0) as u8;
+                data.extend_from_slice(&[brown_r, brown_g, brown_b, 255]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_water_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let wave1 = ((x as f32 * 0.4).sin() + (y as f32 * 0.3).sin()) * 0.3;
+                let wave2 = ((x as f32 * 0.2 + y as f32 * 0.2).sin()) * 0.2;
+                let wave_intensity = (wave1 + wave2).clamp(-0.5, 0.5);
+                let base_red = 40 + (wave_intensity * 20.0) as i32;
+                let base_green = 140 + (wave_intensity * 30.0) as i32;
+                let base_blue = 200;
+                let base_alpha = 200;
+                data.extend_from_slice(&[ base_red.clamp(20,80) as u8, base_green.clamp(120,180) as u8, base_blue, base_alpha ]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_lava_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let noise1 = ((x * 31 + y * 17) % 32) as f32 / 32.0;
+                let noise2 = ((x * 13 + y * 29) % 16) as f32 / 16.0;
+                let intensity = (noise1 + noise2 * 0.6).clamp(0.0, 1.0);
+                if intensity > 0.7 {
+                    data.extend_from_slice(&[255, 255, (150.0 + intensity * 105.0) as u8, 255]);
+                } else if intensity > 0.4 {
+                    data.extend_from_slice(&[255, (120.0 + intensity * 135.0) as u8, 30, 255]);
+                } else {
+                    data.extend_from_slice(&[(180.0 + intensity * 75.0) as u8, 20, 0, 255]);
+                }
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    // Tira vertical de cuadros al estilo de las texturas animadas de Minecraft
+    pub fn create_lava_strip(frames: u32) -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4 * frames) as usize);
+        for frame in 0..frames {
+            for y in 0..size {
+                for x in 0..size {
+                    let fx = x + frame * 3;
+                    let fy = y + frame * 5;
+                    let noise1 = ((fx * 31 + fy * 17) % 32) as f32 / 32.0;
+                    let noise2 = ((fx * 13 + y * 29) % 16) as f32 / 16.0;
+                    let intensity = (noise1 + noise2 * 0.6).clamp(0.0, 1.0);
+                    if intensity > 0.7 {
+                        data.extend_from_slice(&[255, 255, (150.0 + intensity * 105.0) as u8, 255]);
+                    } else if intensity > 0.4 {
+                        data.extend_from_slice(&[255, (120.0 + intensity * 135.0) as u8, 30, 255]);
+                    } else {
+                        data.extend_from_slice(&[(180.0 + intensity * 75.0) as u8, 20, 0, 255]);
+                    }
+                }
+            }
+        }
+        Texture { width: size, height: size * frames, data }
+    }
+    
+    // Divide una tira vertical (alto múltiplo del ancho) en cuadros cuadrados
+    pub fn split_vertical_strip(&self) -> Vec<Texture> {
+        if self.height <= self.width || !self.height.is_multiple_of(self.width) {
+            return vec![self.clone()];
+        }
+        let frame_bytes = (self.width * self.width * 4) as usize;
+        self.data
+            .chunks(frame_bytes)
+            .map(|chunk| Texture { width: self.width, height: self.width, data: chunk.to_vec() })
+            .collect()
+    }
+    
+    pub fn create_obsidian_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let noise1 = ((x * 43 + y * 23) % 16) as f32 / 16.0;
+                let noise2 = ((x * 17 + y * 31) % 8) as f32 / 8.0;
+                let combined_noise = (noise1 + noise2 * 0.2).clamp(0.0, 1.0);
+                
+                let base_intensity = 15.0 + combined_noise * 25.0;
+                let purple_tint = if combined_noise > 0.8 { 20.0 } else { 5.0 };
+                
+                let r = (base_intensity + purple_tint * 0.6) as u8;
+                let g = base_intensity as u8;
+                let b = (base_intensity + purple_tint) as u8;
+                
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_wood_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                // Vetas horizontales con una separación oscura entre tablones
+                let grain = ((y as f32 * 0.8 + (x as f32 * 0.3).sin() * 1.5).sin() + 1.0) * 0.5;
+                let seam = if y % 8 == 0 { 0.55 } else { 1.0 };
+                let r = ((120.0 + grain * 40.0) * seam) as u8;
+                let g = ((78.0 + grain * 25.0) * seam) as u8;
+                let b = ((38.0 + grain * 15.0) * seam) as u8;
+                data.extend_from_slice(&[r, g, b, 255]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_glass_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let border = x == 0 || y == 0 || x == size - 1 || y == size - 1;
+                let streak = (x + y) % 11 == 0 && x > 4 && x < 14;
+                if border {
+                    data.extend_from_slice(&[200, 220, 225, 255]);
+                } else if streak {
+                    data.extend_from_slice(&[250, 255, 255, 255]);
+                } else {
+                    data.extend_from_slice(&[235, 245, 250, 255]);
+                }
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_metal_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let brushed = ((x * 7 + y * 3) % 5) as f32 / 5.0;
+                let rivet = (x % 16 == 3 || x % 16 == 12) && (y % 16 == 3 || y % 16 == 12);
+                let base = if rivet { 150.0 } else { 180.0 + brushed * 25.0 };
+                data.extend_from_slice(&[base as u8, base as u8, (base + 8.0).min(255.0) as u8, 255]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_leaves_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let noise1 = ((x * 37 + y * 11) % 17) as f32 / 17.0;
+                let noise2 = ((x * 5 + y * 23) % 13) as f32 / 13.0;
+                let combined_noise = (noise1 * 0.7 + noise2 * 0.3).clamp(0.0, 1.0);
+                // Huecos entre las hojas: alfa 0 donde el ruido es bajo
+                let alpha = if combined_noise < 0.3 { 0 } else { 255 };
+                let g = (100.0 + combined_noise * 70.0) as u8;
+                let r = (30.0 + combined_noise * 30.0) as u8;
+                let b = (20.0 + combined_noise * 20.0) as u8;
+                data.extend_from_slice(&[r, g, b, alpha]);
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_stained_glass_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        let panes = [[200, 40, 60], [40, 90, 210], [230, 190, 40], [40, 170, 90]];
+        for y in 0..size {
+            for x in 0..size {
+                // Marco de plomo opaco y cuatro vidrios de color translúcidos
+                let lead = x < 2 || y < 2 || x >= size - 2 || y >= size - 2 || x == size / 2 || y == size / 2;
+                if lead {
+                    data.extend_from_slice(&[35, 35, 40, 255]);
+                } else {
+                    let pane = panes[((y >= size / 2) as usize) * 2 + (x >= size / 2) as usize];
+                    data.extend_from_slice(&[pane[0], pane[1], pane[2], 90]);
+                }
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_grate_texture() -> Self {
+        let size = 32;
+        let mut data = Vec::with_capacity((size * size * 4) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let bar = x % 8 < 2 || y % 8 < 2;
+                if bar {
+                    let shade = (60 + ((x * 3 + y * 5) % 7) * 4) as u8;
+                    data.extend_from_slice(&[shade, shade, shade + 6, 255]);
+                } else {
+                    data.extend_from_slice(&[0, 0, 0, 0]);
+                }
+            }
+        }
+        Texture { width: 32, height: 32, data }
+    }
+    
+    pub fn create_procedural(material_type: MaterialType) -> Self {
+        match material_type {
+            MaterialType::Grass => Self::create_grass_texture(),
+            MaterialType::Dirt => Self::create_dirt_texture(),
+            MaterialType::Stone => Self::create_stone_texture(),
+            MaterialType::Water => Self::create_water_texture(),
+            MaterialType::Lava => Self::create_lava_strip(8),
+            MaterialType::Wood => Self::create_wood_texture(),
+            MaterialType::Glass => Self::create_glass_texture(),
+            MaterialType::Metal => Self::create_metal_texture(),
+            MaterialType::Obsidian => Self::create_obsidian_texture(),
+            MaterialType::Leaves => Self::create_leaves_texture(),
+            MaterialType::StainedGlass => Self::create_stained_glass_texture(),
+            MaterialType::Grate => Self::create_grate_texture(),
+        }
+    }
+}
+
+// Cadena de mipmaps generada al cargar; el nivel 0 es la textura original
+#[derive(Clone)]
+pub struct MipChain {
+    pub levels: Vec<Texture>,
+}
+
+impl MipChain {
+    pub fn generate(base: Texture) -> Self {
+        let mut levels = vec![base];
+        loop {
+            let last = levels.last().unwrap();
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        MipChain { levels }
+    }
+    
+    pub fn base(&self) -> &Texture {
+        &self.levels[0]
+    }
+    
+    // `footprint` es el tamaño de un píxel de pantalla en unidades UV; devuelve el color y el alfa (0-1)
+    pub fn sample(&self, u: f32, v: f32, footprint: f32, filter: TextureFilter) -> (Color, f32) {
+        let [r, g, b, a] = match filter {
+            TextureFilter::Nearest => {
+                let base = self.base();
+                let color = base.sample(u, v);
+                [color.r as f32, color.g as f32, color.b as f32, base.sample_alpha(u, v) * 255.0]
+            }
+            TextureFilter::Bilinear => self.base().sample_bilinear_rgba(u, v),
+            TextureFilter::Trilinear => {
+                let base = self.base();
+                let texels = footprint * base.width.max(base.height) as f32;
+                let lod = texels.max(1e-6).log2().clamp(0.0, (self.levels.len() - 1) as f32);
+                let level = lod.floor() as usize;
+                let blend = lod - level as f32;
+                let near = self.levels[level].sample_bilinear_rgba(u, v);
+                if blend <= 0.0 || level + 1 >= self.levels.len() {
+                    near
+                } else {
+                    let far = self.levels[level + 1].sample_bilinear_rgba(u, v);
+                    let mut out = [0.0; 4];
+                    for i in 0..4 {
+                        out[i] = near[i] + (far[i] - near[i]) * blend;
+                    }
+                    out
+                }
+            }
+        };
+        (Color::new(r.round() as u8, g.round() as u8, b.round() as u8), a / 255.0)
+    }
+    
+    // Nivel cuyo texel se aproxima más a la huella del píxel
+    pub fn level_for(&self, footprint: f32) -> &Texture {
+        let base = self.base();
+        let texels = footprint * base.width.max(base.height) as f32;
+        let lod = texels.max(1e-6).log2().clamp(0.0, (self.levels.len() - 1) as f32);
+        &self.levels[lod.round() as usize]
+    }
+}
+
+const DEFAULT_BUMP_STRENGTH: f32 = 2.5;
+
+// Marco local del punto de impacto: normal geométrica, tangentes según (u, v) y huella del píxel en texeles
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceFrame {
+    pub normal: Vec3,
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    pub u: f32,
+    pub v: f32,
+    pub footprint: f32,
+}
+
+// Detalle de superficie opcional: mapa de normales en espacio tangente o mapa de alturas
+#[derive(Clone)]
+pub enum SurfaceDetail {
+    NormalMap(MipChain),
+    BumpMap { height: MipChain, strength: f32 },
+}
+
+impl SurfaceDetail {
+    pub fn perturb_normal(&self, frame: &SurfaceFrame) -> Vec3 {
+        let SurfaceFrame { normal, tangent, bitangent, u, v, footprint } = *frame;
+        match self {
+            SurfaceDetail::NormalMap(map) => {
+                let [r, g, b, _] = map.level_for(footprint).sample_bilinear_rgba(u, v);
+                let x = r / 255.0 * 2.0 - 1.0;
+                let y = g / 255.0 * 2.0 - 1.0;
+                let z = b / 255.0 * 2.0 - 1.0;
+                // Convención OpenGL: el verde apunta hacia arriba en la imagen, es decir, hacia -v
+                nalgebra_glm::normalize(&(tangent * x - bitangent * y + normal * z))
+            }
+            SurfaceDetail::BumpMap { height, strength } => {
+                let level = height.level_for(footprint);
+                let du = 1.0 / level.width as f32;
+                let dv = 1.0 / level.height as f32;
+                let h = |su: f32, sv: f32| {
+                    let [r, g, b, _] = level.sample_bilinear_rgba(su, sv);
+                    (0.299 * r + 0.587 * g + 0.114 * b) / 255.0
+                };
+                let dh_du = (h(u + du, v) - h(u - du, v)) * 0.5;
+                let dh_dv = (h(u, v + dv) - h(u, v - dv)) * 0.5;
+                nalgebra_glm::normalize(&(normal - (tangent * dh_du + bitangent * dh_dv) * *strength))
+            }
+        }
+    }
+}
+
+// Animación de la textura de un material en función del tiempo global (en segundos)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextureAnimation {
+    pub frame_duration: f32,
+    pub scroll: (f32, f32),
+    pub flow_amplitude: f32,
+    pub flow_speed: f32,
+}
+
+impl TextureAnimation {
+    pub fn for_material(material_type: MaterialType) -> Self {
+        match material_type {
+            MaterialType::Lava => TextureAnimation {
+                frame_duration: 0.15,
+                scroll: (0.0, 0.04),
+                flow_amplitude: 0.03,
+                flow_speed: 1.2,
+            },
+            MaterialType::Water => TextureAnimation {
+                frame_duration: 0.1,
+                scroll: (0.06, 0.03),
+                flow_amplitude: 0.02,
+                flow_speed: 2.0,
+            },
+            _ => TextureAnimation::default(),
+        }
+    }
+    
+    pub fn transform_uv(&self, u: f32, v: f32, time: f32) -> (f32, f32) {
+        let mut u = u + self.scroll.0 * time;
+        let mut v = v + self.scroll.1 * time;
+        if self.flow_amplitude > 0.0 {
+            // Flujo procedural: ondas cruzadas que desplazan las UV
+            let phase = time * self.flow_speed;
+            u += (v * TAU + phase).sin() * self.flow_amplitude;
+            v += (u * TAU * 0.7 + phase * 1.3).cos() * self.flow_amplitude;
+        }
+        (u, v)
+    }
+    
+    pub fn frame_index(&self, frame_count: usize, time: f32) -> usize {
+        if frame_count <= 1 || self.frame_duration <= 0.0 {
+            return 0;
+        }
+        ((time / self.frame_duration).floor() as i64).rem_euclid(frame_count as i64) as usize
+    }
+}
+
+#[derive(Clone)]
+pub struct AnimatedTexture {
+    pub frames: Vec<MipChain>,
+    pub animation: TextureAnimation,
+}
+
+impl AnimatedTexture {
+    pub fn new(texture: Texture, animation: TextureAnimation) -> Self {
+        let frames = texture.split_vertical_strip().into_iter().map(MipChain::generate).collect();
+        AnimatedTexture { frames, animation }
+    }
+    
+    pub fn frame_at(&self, u: f32, v: f32, time: f32) -> (&MipChain, f32, f32) {
+        let (u, v) = self.animation.transform_uv(u, v, time);
+        let index = self.animation.frame_index(self.frames.len(), time);
+        (&self.frames[index], u, v)
+    }
+}
+
+// Texturas indexadas por el ID del material; agregar un bloque nuevo solo requiere registrarlo aquí
+#[derive(Clone)]
+pub struct TextureRegistry {
+    textures: Vec<Option<AnimatedTexture>>,
+    details: Vec<Option<SurfaceDetail>>,
+    pub filter: TextureFilter,
+}
+
+impl TextureRegistry {
+    pub fn new() -> Self {
+        TextureRegistry {
+            textures: vec![None; MaterialType::COUNT],
+            details: vec![None; MaterialType::COUNT],
+            filter: TextureFilter::Nearest,
+        }
+    }
+    
+    // Carga `<nombre>.png` para cada material y usa la textura procedural si no existe
+    pub fn load_default() -> Self {
+        let mut registry = Self::new();
+        for material_type in MaterialType::ALL {
+            let path = format!("{}.png", material_type.name());
+            let texture = match Texture::load_from_file(&path) {
+                Ok(tex) => tex,
+                Err(_) => Texture::create_procedural(material_type),
+            };
+            
+            // Relieve opcional: `<nombre>_normal.png` tiene prioridad sobre `<nombre>_height.png`
+            let normal_path = format!("{}_normal.png", material_type.name());
+            let height_path = format!("{}_height.png", material_type.name());
+            if let Ok(normal_map) = Texture::load_from_file(&normal_path) {
+                registry.register_detail(material_type, SurfaceDetail::NormalMap(MipChain::generate(normal_map)));
+            } else if let Ok(height) = Texture::load_from_file(&height_path) {
+                registry.register_detail(material_type, SurfaceDetail::BumpMap {
+                    height: MipChain::generate(height),
+                    strength: DEFAULT_BUMP_STRENGTH,
+                });
+            }
+            
+            registry.register(material_type, texture);
+        }
+        registry
+    }
+    
+    // Las tiras verticales se dividen en cuadros y se reproducen según `TextureAnimation::for_material`
+    pub fn register(&mut self, material_type: MaterialType, texture: Texture) {
+        let animation = TextureAnimation::for_material(material_type);
+        self.textures[material_type.id()] = Some(AnimatedTexture::new(texture, animation));
+    }
+    
+    pub fn register_detail(&mut self, material_type: MaterialType, detail: SurfaceDetail) {
+        self.details[material_type.id()] = Some(detail);
+    }
+    
+    pub fn get(&self, material_type: MaterialType) -> Option<&AnimatedTexture> {
+        self.textures[material_type.id()].as_ref()
+    }
+    
+    pub fn sample(&self, material: &Material, u: f32, v: f32, footprint: f32, time: f32) -> (Color, f32) {
+        if !material.has_texture {
+            return (material.diffuse, 1.0);
+        }
+        match self.get(material.material_type) {
+            Some(animated) => {
+                let (mips, u, v) = animated.frame_at(u, v, time);
+                mips.sample(u, v, footprint, self.filter)
+            }
+            None => (material.diffuse, 1.0),
+        }
+    }
+    
+    // Alfa exacto en el nivel base, usado por los rayos de sombra sobre materiales recortados
+    pub fn alpha_at(&self, material: &Material, u: f32, v: f32, time: f32) -> f32 {
+        if !material.has_texture {
+            return 1.0;
+        }
+        match self.get(material.material_type) {
+            Some(animated) => {
+                let (mips, u, v) = animated.frame_at(u, v, time);
+                mips.base().sample_alpha(u, v)
+            }
+            None => 1.0,
+        }
+    }
+    
+    pub fn perturb_normal(&self, material: &Material, frame: &SurfaceFrame) -> Vec3 {
+        match &self.details[material.material_type.id()] {
+            Some(detail) => detail.perturb_normal(frame),
+            None => frame.normal,
+        }
+    }
+}